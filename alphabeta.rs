@@ -0,0 +1,479 @@
+use mcts::GameState;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::endgame::Proven;
+use crate::moves::Move;
+use crate::quorridor::{Quorridor, shortest_path_to_goal, move_delta};
+use crate::wall::Orientation;
+use crate::notation::{move_to_notation, line_to_notation};
+use crate::search::StopReason;
+use crate::search_result::{SearchResult, RootMoveStats, win_probability, PV_LENGTH};
+use crate::time_control::SearchLimits;
+
+// Negamax scores are from the side to move, in the same units as the MCTS
+// heuristic: 1000 per step of shortest-path difference, MATE_SCORE for a win
+pub const MATE_SCORE: i64 = 100000;
+const INFINITY: i64 = MATE_SCORE + 1;
+const STEP_SCORE: i64 = 1000;
+
+// Depth searched when the limits set neither a depth nor a time budget
+pub const DEFAULT_DEPTH: u32 = 3;
+pub const MAX_DEPTH: u32 = 64;
+pub const DEFAULT_AB_TABLE_MB: usize = 32;
+
+// How often (in nodes) the deadline and stop flag are checked
+const CHECK_INTERVAL: u64 = 1024;
+const MOVE_KEYS: usize = 256;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,  // Failed high: the score is at least this
+    Upper,  // Failed low: the score is at most this
+}
+
+#[derive(Clone, Debug)]
+struct TableEntry {
+    key: u64,
+    depth: u32,
+    score: i64,
+    bound: Bound,
+    best: Option<Move>,
+}
+
+// Index of a move in the history table. Pawn moves are keyed by their offset,
+// walls by square and orientation.
+fn move_key(mov: &Move) -> usize {
+    match mov {
+        Move::PlaceWall(x, y, orientation) => {
+            let o = if *orientation == Orientation::Horizontal { 0 } else { 1 };
+            25 + ((y / 2 * 10 + x / 2) * 2 + o) as usize
+        }
+        _ => {
+            let (dx, dy) = move_delta(mov).unwrap();
+            ((dx / 2 + 2) * 5 + dy / 2 + 2) as usize
+        }
+    }
+}
+
+// Mate scores are stored relative to the node so they stay valid at any ply
+fn score_to_table(score: i64, ply: u32) -> i64 {
+    if score > MATE_SCORE - 1000 { score + ply as i64 } else if score < -MATE_SCORE + 1000 { score - ply as i64 } else { score }
+}
+
+fn score_from_table(score: i64, ply: u32) -> i64 {
+    if score > MATE_SCORE - 1000 { score - ply as i64 } else if score < -MATE_SCORE + 1000 { score + ply as i64 } else { score }
+}
+
+// A root score that found the end of the game, as plies to the goal
+fn proven_from_score(score: i64) -> Option<Proven> {
+    if score > MATE_SCORE - 1000 {
+        Some(Proven::Win((MATE_SCORE - score) as u32))
+    } else if score < -MATE_SCORE + 1000 {
+        Some(Proven::Loss((MATE_SCORE + score) as u32))
+    } else {
+        None
+    }
+}
+
+// Path-difference evaluation from the side to move
+pub fn evaluate(state: &Quorridor) -> i64 {
+    let me = state.active_player;
+    match (shortest_path_to_goal(state, me), shortest_path_to_goal(state, 1 - me)) {
+        (Some(mine), Some(theirs)) => (theirs as i64 - mine as i64) * STEP_SCORE,
+        _ => 0,  // Unreachable: walls that cut off a goal are never searched
+    }
+}
+
+// Iterative-deepening negamax with alpha-beta pruning, a transposition table,
+// and killer/history move ordering. Tables persist between searches of the
+// same game; call new_game to clear them.
+pub struct AlphaBeta {
+    table: Vec<Option<TableEntry>>,
+    killers: Vec<[Option<Move>; 2]>,
+    history: [[i64; MOVE_KEYS]; 2],
+    stop: Arc<AtomicBool>,
+    path: Vec<u64>,
+    nodes: u64,
+    deadline: Option<Instant>,
+    aborted: bool,
+}
+
+impl AlphaBeta {
+    pub fn new() -> Self {
+        AlphaBeta::with_table_mb(DEFAULT_AB_TABLE_MB)
+    }
+
+    pub fn with_table_mb(megabytes: usize) -> Self {
+        let entries = (megabytes * 1024 * 1024 / std::mem::size_of::<Option<TableEntry>>()).max(1);
+        AlphaBeta {
+            table: vec![None; entries],
+            killers: vec![[None, None]; MAX_DEPTH as usize + 1],
+            history: [[0; MOVE_KEYS]; 2],
+            stop: Arc::new(AtomicBool::new(false)),
+            path: Vec::new(),
+            nodes: 0,
+            deadline: None,
+            aborted: false,
+        }
+    }
+
+    // Setting this flag makes a running search return its last completed depth
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn new_game(&mut self) {
+        self.table.iter_mut().for_each(|entry| *entry = None);
+        self.history = [[0; MOVE_KEYS]; 2];
+    }
+
+    pub fn search(&mut self, state: &Quorridor, limits: &SearchLimits) -> SearchResult {
+        let start = Instant::now();
+        let budget = limits.budget(state);
+        let max_depth = match (limits.depth, budget) {
+            (Some(depth), _) => depth.clamp(1, MAX_DEPTH),
+            (None, Some(_)) => MAX_DEPTH,
+            (None, None) => DEFAULT_DEPTH,
+        };
+        self.deadline = budget.map(|b| start + b.hard);
+        self.aborted = false;
+        self.nodes = 0;
+        self.path.clear();
+        self.killers.iter_mut().for_each(|k| *k = [None, None]);
+        for scores in self.history.iter_mut() {
+            scores.iter_mut().for_each(|h| *h /= 8);
+        }
+
+        let mut completed: Option<(u32, Vec<RootScore>)> = None;
+        let mut stop_reason = StopReason::DepthLimit;
+        for depth in 1..=max_depth {
+            let root_scores = self.search_root(state, depth);
+            if self.aborted {
+                // Moves already searched at the first depth beat having no move at all
+                if completed.is_none() {
+                    completed = Some((0, root_scores));
+                }
+                stop_reason = if self.stop.load(Ordering::Relaxed) { StopReason::Stopped } else { StopReason::HardDeadline };
+                break;
+            }
+            let decided = root_scores.first().is_some_and(|best| best.score.abs() > MATE_SCORE - 1000);
+            completed = Some((depth, root_scores));
+            if decided {
+                stop_reason = StopReason::BestMoveDecided;
+                break;
+            }
+            // Another iteration costs several times the last one; skip it past the soft deadline
+            if budget.is_some_and(|b| start.elapsed() >= b.soft) {
+                stop_reason = StopReason::SoftDeadline;
+                break;
+            }
+        }
+
+        let (depth, root_scores) = completed.unwrap_or_default();
+        self.result(state, depth, root_scores, start.elapsed(), stop_reason)
+    }
+
+    fn result(&self, state: &Quorridor, depth: u32, root_scores: Vec<RootScore>, elapsed: Duration, stop_reason: StopReason) -> SearchResult {
+        // Stopped before a single root move finished: any legal move will do
+        let best_move = root_scores.first().map(|r| r.mov.clone())
+            .or_else(|| state.available_moves().into_iter().find(|m| play(state, m).is_some()));
+        let principal_variation = self.principal_variation(state, depth.max(1) as usize);
+        let score = root_scores.first().map_or(0, |r| r.score);
+        // A move that failed low only bounds its score from above, so it gets no value
+        let root_moves: Vec<RootMoveStats> = root_scores.iter()
+            .map(|r| {
+                let exact = r.bound == Bound::Exact;
+                RootMoveStats {
+                    mov: r.mov.clone(),
+                    notation: move_to_notation(state, &r.mov),
+                    visits: r.nodes,
+                    inherited_visits: 0,
+                    q: exact.then_some(r.score as f64),
+                    win_probability: exact.then(|| win_probability(r.score as f64)),
+                    proven: if exact { proven_from_score(r.score) } else { None },
+                }
+            })
+            .collect();
+        let secs = elapsed.as_secs_f64();
+
+        SearchResult {
+            best_move_notation: best_move.as_ref().map(|m| move_to_notation(state, m)),
+            best_move,
+            principal_variation_notation: line_to_notation(state, &principal_variation),
+            principal_variation,
+            root_moves,
+            win_probability: win_probability(score as f64),
+            playouts: self.nodes,
+            inherited_visits: 0,
+            elapsed_ms: secs * 1000.0,
+            playouts_per_second: if secs > 0.0 { self.nodes as f64 / secs } else { 0.0 },
+            tree_size: self.table.iter().filter(|e| e.is_some()).count(),
+            stop_reason,
+            transposition: None,
+            depth: Some(depth),
+            proven: proven_from_score(score),
+        }
+    }
+
+    // Principal variation search: the first move gets a full window and the
+    // rest a null window around the best score so far, searched again with a
+    // full window when they beat it. Scores of moves that fail low are upper
+    // bounds, all others exact; returns them best first, exact before bound on a tie
+    fn search_root(&mut self, state: &Quorridor, depth: u32) -> Vec<RootScore> {
+        let table_move = self.probe(state.zobrist).and_then(|e| e.best.clone());
+        let moves = self.ordered_moves(state, 0, table_move);
+        let mut scores = Vec::with_capacity(moves.len());
+        let mut alpha = -INFINITY;
+        self.path.push(state.zobrist);
+
+        for mov in moves {
+            let Some(child) = play(state, &mov) else { continue };
+            let before = self.nodes;
+            let mut score = alpha;
+            let mut bound = Bound::Upper;
+            if !scores.is_empty() {
+                score = -self.negamax(&child, depth - 1, 1, -alpha - 1, -alpha);
+            }
+            if scores.is_empty() || (score > alpha && !self.aborted) {
+                score = -self.negamax(&child, depth - 1, 1, -INFINITY, INFINITY);
+                bound = Bound::Exact;
+            }
+            if self.aborted {
+                break;
+            }
+            alpha = alpha.max(score);
+            scores.push(RootScore { mov, score, bound, nodes: self.nodes - before });
+        }
+        self.path.pop();
+
+        scores.sort_by_key(|r| std::cmp::Reverse((r.score, r.bound == Bound::Exact)));
+        if let Some(best) = scores.first() {
+            self.store(state.zobrist, depth, best.score, Bound::Exact, Some(best.mov.clone()), 0);
+        }
+        scores
+    }
+
+    fn negamax(&mut self, state: &Quorridor, depth: u32, ply: u32, mut alpha: i64, beta: i64) -> i64 {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) && self.should_stop() {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        // The previous mover reached their goal
+        if state.game_over() {
+            return -(MATE_SCORE - ply as i64);
+        }
+        // Pawns shuffling back and forth: a repetition is a draw, as in MCTS
+        if self.path.contains(&state.zobrist) {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_DEPTH {
+            return evaluate(state);
+        }
+
+        let original_alpha = alpha;
+        let mut table_move = None;
+        if let Some(entry) = self.probe(state.zobrist) {
+            table_move = entry.best.clone();
+            if entry.depth >= depth {
+                let score = score_from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let moves = self.ordered_moves(state, ply as usize, table_move);
+
+        self.path.push(state.zobrist);
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for mov in moves {
+            let Some(child) = play(state, &mov) else { continue };
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha);
+            if self.aborted {
+                self.path.pop();
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mov.clone());
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                self.record_cutoff(state.active_player, &mov, depth, ply as usize);
+                break;
+            }
+        }
+        self.path.pop();
+
+        if best_move.is_none() {
+            // No legal move: treat as a loss for the side to move
+            return -(MATE_SCORE - ply as i64);
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store(state.zobrist, depth, best_score, bound, best_move, ply);
+        best_score
+    }
+
+    fn should_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    // Table move first, then killers, then by history score; pawn moves win ties with walls
+    fn ordered_moves(&self, state: &Quorridor, ply: usize, table_move: Option<Move>) -> Vec<Move> {
+        let mut moves = state.available_moves();
+        let player = state.active_player;
+        let killers = &self.killers[ply.min(MAX_DEPTH as usize)];
+        moves.sort_by_cached_key(|mov| {
+            let first = table_move.as_ref() == Some(mov);
+            let killer = killers.iter().any(|k| k.as_ref() == Some(mov));
+            let pawn = !matches!(mov, Move::PlaceWall(..));
+            std::cmp::Reverse((first, killer, self.history[player][move_key(mov)], pawn))
+        });
+        moves
+    }
+
+    fn record_cutoff(&mut self, player: usize, mov: &Move, depth: u32, ply: usize) {
+        self.history[player][move_key(mov)] += (depth * depth) as i64;
+        let killers = &mut self.killers[ply.min(MAX_DEPTH as usize)];
+        if killers[0].as_ref() != Some(mov) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(mov.clone());
+        }
+    }
+
+    fn probe(&self, key: u64) -> Option<&TableEntry> {
+        self.table[(key % self.table.len() as u64) as usize].as_ref().filter(|e| e.key == key)
+    }
+
+    // Depth-preferred replacement, except that a different position always takes the slot
+    fn store(&mut self, key: u64, depth: u32, score: i64, bound: Bound, best: Option<Move>, ply: u32) {
+        let index = (key % self.table.len() as u64) as usize;
+        if self.table[index].as_ref().is_some_and(|e| e.key == key && e.depth > depth) {
+            return;
+        }
+        self.table[index] = Some(TableEntry { key, depth, score: score_to_table(score, ply), bound, best });
+    }
+
+    // Best moves followed through the table from `state`
+    fn principal_variation(&self, state: &Quorridor, max_len: usize) -> Vec<Move> {
+        let mut line = Vec::new();
+        let mut current = state.clone();
+        let mut seen = vec![current.zobrist];
+        while line.len() < max_len.min(PV_LENGTH) {
+            let Some(mov) = self.probe(current.zobrist).and_then(|e| e.best.clone()) else { break };
+            let Some(next) = play(&current, &mov) else { break };
+            if seen.contains(&next.zobrist) {
+                break;
+            }
+            seen.push(next.zobrist);
+            line.push(mov);
+            current = next;
+        }
+        line
+    }
+}
+
+impl Default for AlphaBeta {
+    fn default() -> Self {
+        AlphaBeta::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RootScore {
+    mov: Move,
+    score: i64,
+    bound: Bound,  // Exact, or Upper for a move that failed low
+    nodes: u64,
+}
+
+// The position after `mov`, or None if it is a wall that cuts off a goal
+fn play(state: &Quorridor, mov: &Move) -> Option<Quorridor> {
+    let mut child = state.clone();
+    child.make_move(mov);
+    if matches!(mov, Move::PlaceWall(..)) && !child.both_players_have_path() {
+        return None;
+    }
+    Some(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endgame::test_race;
+
+    #[test]
+    fn test_finds_winning_step() {
+        // Player 0 one step from the goal row, player 1 far from theirs
+        let game = test_race((1, 15), (17, 15), 0);
+
+        let mut engine = AlphaBeta::with_table_mb(1);
+        let result = engine.search(&game, &SearchLimits::depth(2));
+        assert_eq!(result.best_move, Some(Move::Down));
+        assert!(result.root_moves[0].q.unwrap() > (MATE_SCORE - 1000) as f64);
+    }
+
+    #[test]
+    fn test_deeper_search_keeps_a_legal_move() {
+        let game = Quorridor::default();
+        let mut engine = AlphaBeta::with_table_mb(1);
+        let result = engine.search(&game, &SearchLimits::depth(2));
+        let best = result.best_move.expect("a move from the start position");
+        assert!(game.available_moves().contains(&best));
+        assert_eq!(result.depth, Some(2));
+    }
+
+    #[test]
+    fn test_root_scores_match_a_full_window_search() {
+        let mut game = Quorridor::default();
+        for mov in [Move::Down, Move::Up, Move::PlaceWall(8, 4, Orientation::Horizontal), Move::Up] {
+            game.make_move(&mov);
+        }
+        let depth = 3;
+        let mut engine = AlphaBeta::with_table_mb(1);
+        let result = engine.search(&game, &SearchLimits::depth(depth));
+        assert!(result.root_moves[0].q.is_some());
+        assert!(result.root_moves.iter().any(|m| m.q.is_none()));
+
+        // Each root move searched on its own with a fresh table and a full window.
+        // Reported scores are exact; moves that failed low report none and score no
+        // better than the best move.
+        let best = result.root_moves[0].q.unwrap();
+        for root_move in &result.root_moves {
+            let mut reference = AlphaBeta::with_table_mb(1);
+            reference.path.push(game.zobrist);
+            let child = play(&game, &root_move.mov).unwrap();
+            let score = -reference.negamax(&child, depth - 1, 1, -INFINITY, INFINITY) as f64;
+            match root_move.q {
+                Some(q) => {
+                    assert_eq!(q, score, "{}", root_move.notation);
+                    assert_eq!(root_move.win_probability, Some(win_probability(score)));
+                }
+                None => {
+                    assert!(score <= best, "{}", root_move.notation);
+                    assert!(root_move.win_probability.is_none() && root_move.proven.is_none());
+                }
+            }
+        }
+    }
+}
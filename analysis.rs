@@ -0,0 +1,275 @@
+// Post-game analysis: a fixed search on every position of a record, each move
+// scored by how much of the mover's win probability it gave away and classed
+// from best to blunder, with a summary per player.
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+use crate::engine::{Engine, position_after};
+use crate::game_record::GameRecord;
+use crate::moves::Move;
+use crate::notation::move_to_notation;
+use crate::quorridor::Quorridor;
+use crate::search_result::SearchResult;
+use crate::time_control::SearchLimits;
+
+// Win probability a move may give away and still count as good, an inaccuracy or a mistake
+pub const INACCURACY_LOSS: f64 = 0.05;
+pub const MISTAKE_LOSS: f64 = 0.12;
+pub const BLUNDER_LOSS: f64 = 0.25;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveClass {
+    Best,  // The engine's preferred move
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+pub const MOVE_CLASSES: [MoveClass; 5] = [MoveClass::Best, MoveClass::Good, MoveClass::Inaccuracy, MoveClass::Mistake, MoveClass::Blunder];
+
+impl MoveClass {
+    pub fn classify(loss: f64, preferred: bool) -> Self {
+        if preferred {
+            MoveClass::Best
+        } else if loss >= BLUNDER_LOSS {
+            MoveClass::Blunder
+        } else if loss >= MISTAKE_LOSS {
+            MoveClass::Mistake
+        } else if loss >= INACCURACY_LOSS {
+            MoveClass::Inaccuracy
+        } else {
+            MoveClass::Good
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveClass::Best => "best",
+            MoveClass::Good => "good",
+            MoveClass::Inaccuracy => "inaccuracy",
+            MoveClass::Mistake => "mistake",
+            MoveClass::Blunder => "blunder",
+        }
+    }
+
+    // Suffix for the move in annotated move lists
+    pub fn symbol(self) -> &'static str {
+        match self {
+            MoveClass::Best | MoveClass::Good => "",
+            MoveClass::Inaccuracy => "?!",
+            MoveClass::Mistake => "?",
+            MoveClass::Blunder => "??",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MoveAnalysis {
+    pub ply: usize,  // From 1
+    pub player: usize,
+    #[serde(rename = "move")]
+    pub notation: String,
+    pub eval_before: f64,  // The mover's win probability before and after the move
+    pub eval_after: f64,
+    pub best_move: Option<String>,  // The engine's preferred move in the position before
+    pub best_line: Vec<String>,
+    pub loss: f64,
+    pub class: MoveClass,
+}
+
+impl fmt::Display for MoveAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>3}. P{} {:<8} {:>3.0}% -> {:>3.0}%  {:<10}", self.ply, self.player, format!("{}{}", self.notation, self.class.symbol()),
+            self.eval_before * 100.0, self.eval_after * 100.0, self.class.name())?;
+        if self.class != MoveClass::Best && let Some(best) = &self.best_move {
+            write!(f, "  best {}", best)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PositionEval {
+    pub win_probability: f64,  // For the side to move
+    pub search: Option<SearchResult>,  // None for finished games
+}
+
+// Search the position reached by `moves` from `start`. The engine starts afresh,
+// so every position gets the same budget and none depends on those searched before.
+pub fn evaluate_position(start: &Quorridor, moves: &[Move], engine: &mut dyn Engine, limits: &SearchLimits) -> PositionEval {
+    let state = position_after(start, moves);
+    match state.winner() {
+        Some(winner) => PositionEval { win_probability: if winner == state.active_player { 1.0 } else { 0.0 }, search: None },
+        None => {
+            engine.new_game();
+            engine.set_position(start, moves);
+            let search = engine.search(limits);
+            PositionEval { win_probability: search.win_probability, search: Some(search) }
+        }
+    }
+}
+
+// The mover's win probability before and after move `index` (0-based), with
+// evals[ply] for the position after `ply` moves
+pub fn swing(evals: &[Option<PositionEval>], index: usize) -> Option<(f64, f64)> {
+    let before = evals.get(index)?.as_ref()?;
+    let after = evals.get(index + 1)?.as_ref()?;
+    Some((before.win_probability, 1.0 - after.win_probability))
+}
+
+// Move `index` (from 0) of the game `moves` from `start`, if the positions
+// before and after it are evaluated
+pub fn analyze_move(start: &Quorridor, moves: &[Move], evals: &[Option<PositionEval>], index: usize) -> Option<MoveAnalysis> {
+    let (eval_before, eval_after) = swing(evals, index)?;
+    let search = evals[index].as_ref()?.search.as_ref();
+    let best_move = search.and_then(|search| search.best_move_notation.clone());
+    let before = position_after(start, &moves[..index]);
+    let notation = move_to_notation(&before, &moves[index]);
+    let loss = (eval_before - eval_after).max(0.0);
+    Some(MoveAnalysis {
+        ply: index + 1,
+        player: before.active_player,
+        class: MoveClass::classify(loss, best_move.as_ref() == Some(&notation)),
+        best_line: search.map_or(Vec::new(), |search| search.principal_variation_notation.clone()),
+        notation,
+        eval_before,
+        eval_after,
+        best_move,
+        loss,
+    })
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSummary {
+    pub name: String,
+    pub moves: usize,
+    pub best: usize,
+    pub good: usize,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+    pub average_loss: f64,
+}
+
+impl PlayerSummary {
+    pub fn count(&self, class: MoveClass) -> usize {
+        match class {
+            MoveClass::Best => self.best,
+            MoveClass::Good => self.good,
+            MoveClass::Inaccuracy => self.inaccuracies,
+            MoveClass::Mistake => self.mistakes,
+            MoveClass::Blunder => self.blunders,
+        }
+    }
+}
+
+impl fmt::Display for PlayerSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} moves, {} best, {} good, {} inaccuracies, {} mistakes, {} blunders, average loss {:.1}%",
+            self.name, self.moves, self.best, self.good, self.inaccuracies, self.mistakes, self.blunders, self.average_loss * 100.0)
+    }
+}
+
+// The record with every move annotated, one JSON object per line in analysis files
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameAnalysis {
+    #[serde(flatten)]
+    pub record: GameRecord,
+    pub engine: String,
+    pub analysis: Vec<MoveAnalysis>,
+    pub summary: [PlayerSummary; 2],
+}
+
+impl GameAnalysis {
+    // `moves` are those of `record` and evals[ply] the position after `ply` of them
+    pub fn new(record: &GameRecord, moves: &[Move], evals: &[Option<PositionEval>], engine: String) -> Self {
+        let start = Quorridor::default();
+        let analysis: Vec<MoveAnalysis> = (0..moves.len()).filter_map(|index| analyze_move(&start, moves, evals, index)).collect();
+        let summary = [0, 1].map(|player| {
+            let moves: Vec<&MoveAnalysis> = analysis.iter().filter(|m| m.player == player).collect();
+            let count = |class: MoveClass| moves.iter().filter(|m| m.class == class).count();
+            PlayerSummary {
+                name: record.players[player].clone(),
+                moves: moves.len(),
+                best: count(MoveClass::Best),
+                good: count(MoveClass::Good),
+                inaccuracies: count(MoveClass::Inaccuracy),
+                mistakes: count(MoveClass::Mistake),
+                blunders: count(MoveClass::Blunder),
+                average_loss: if moves.is_empty() { 0.0 } else { moves.iter().map(|m| m.loss).sum::<f64>() / moves.len() as f64 },
+            }
+        });
+        GameAnalysis { record: record.clone(), engine, analysis, summary }
+    }
+
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("GameAnalysis is always serializable")
+    }
+}
+
+// Search every position of `record` within `limits`; `progress` hears of each
+// position as it is done
+pub fn analyze_game(record: &GameRecord, engine: &mut dyn Engine, limits: &SearchLimits, mut progress: impl FnMut(usize, usize)) -> Result<GameAnalysis, String> {
+    let moves = record.parse_moves()?;
+    let start = Quorridor::default();
+    let mut evals = Vec::with_capacity(moves.len() + 1);
+    for ply in 0..=moves.len() {
+        evals.push(Some(evaluate_position(&start, &moves[..ply], engine, limits)));
+        progress(ply, moves.len());
+    }
+    Ok(GameAnalysis::new(record, &moves, &evals, engine.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabeta::AlphaBeta;
+    use crate::engine::{AlphaBetaEngine, MctsEngine, DEFAULT_EXPLORATION};
+    use crate::game_record::test_record;
+    use crate::mcts_impl::MyEvaluator;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(MoveClass::classify(0.4, true), MoveClass::Best);
+        assert_eq!(MoveClass::classify(0.01, false), MoveClass::Good);
+        assert_eq!(MoveClass::classify(0.08, false), MoveClass::Inaccuracy);
+        assert_eq!(MoveClass::classify(0.2, false), MoveClass::Mistake);
+        assert_eq!(MoveClass::classify(0.3, false), MoveClass::Blunder);
+    }
+
+    #[test]
+    fn test_positions_are_searched_afresh() {
+        // An MCTS engine would otherwise carry the first tree into the second search
+        let mut engine = MctsEngine::new(MyEvaluator::new(), DEFAULT_EXPLORATION, 1, 1);
+        let start = Quorridor::default();
+        let limits = SearchLimits::playouts(200);
+        let first = evaluate_position(&start, &[], &mut engine, &limits).search.unwrap();
+        let second = evaluate_position(&start, &[first.best_move.unwrap()], &mut engine, &limits).search.unwrap();
+        assert_eq!(second.inherited_visits, 0);
+        assert_eq!(second.playouts, 200);
+    }
+
+    #[test]
+    fn test_analyze_game() {
+        let record = test_record(&["e2", "e8", "a1h"]);
+        let mut engine = AlphaBetaEngine::new(AlphaBeta::new());
+        let mut seen = Vec::new();
+        let analysis = analyze_game(&record, &mut engine, &SearchLimits::depth(2), |ply, _| seen.push(ply)).unwrap();
+        assert_eq!(seen, vec![0, 1, 2, 3]);
+        assert_eq!(analysis.analysis.len(), 3);
+        assert_eq!(analysis.analysis.iter().map(|m| m.player).collect::<Vec<_>>(), vec![0, 1, 0]);
+        for (player, summary) in analysis.summary.iter().enumerate() {
+            assert_eq!(summary.moves, analysis.analysis.iter().filter(|m| m.player == player).count());
+            assert_eq!(MOVE_CLASSES.iter().map(|&class| summary.count(class)).sum::<usize>(), summary.moves);
+        }
+
+        // The analysis line still reads as a game record
+        let line = analysis.to_json_line();
+        let parsed: GameRecord = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.moves, record.moves);
+        let parsed: GameAnalysis = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.analysis, analysis.analysis);
+    }
+}
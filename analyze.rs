@@ -0,0 +1,85 @@
+use std::env;
+use std::io::{self, Write};
+
+use quorridor::{engine_config::EngineConfig, game_record::load_records, analysis::analyze_game};
+
+fn print_usage() {
+    println!("Usage: analyze --record FILE [--game N] [--engine SPEC] [--out FILE] [--quiet]");
+    println!("  Searches every position of the games in FILE (all of them, or game N from 1) and");
+    println!("  classes each move as best, good, inaccuracy, mistake or blunder.");
+    println!("  SPEC is an engine spec with a fixed search, default {}", DEFAULT_SPEC);
+    println!("  --out appends each analyzed game as a JSON line; --quiet prints only the summaries.");
+}
+
+const DEFAULT_SPEC: &str = "mcts:playouts=5000";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let arg = |flag: &str| args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let quiet = args.iter().any(|a| a == "--quiet");
+
+    let Some(path) = arg("--record") else {
+        print_usage();
+        return Ok(());
+    };
+    let records = load_records(&path)?;
+    let selected: Vec<usize> = match arg("--game").map(|n| n.parse::<usize>()) {
+        None => (0..records.len()).collect(),
+        Some(Ok(n)) if n >= 1 && n <= records.len() => vec![n - 1],
+        Some(_) => return Err(format!("--game takes a game number from 1 to {}", records.len())),
+    };
+    let spec = arg("--engine").unwrap_or_else(|| DEFAULT_SPEC.to_string());
+    let config = EngineConfig::parse(&spec)?;
+    let mut engine = config.build().map_err(|e| format!("{}: {}", spec, e))?;
+    let limits = config.limits();
+    let mut out = match arg("--out") {
+        Some(out) => Some(std::fs::File::options().create(true).append(true).open(&out).map_err(|e| format!("{}: {}", out, e))?),
+        None => None,
+    };
+
+    println!("=== Quorridor Analysis ===");
+    println!("  {} game(s) from {}, engine {}", selected.len(), path, spec);
+    let mut skipped = 0;
+    for index in selected {
+        let record = &records[index];
+        println!("\nGame {}: {} vs {}, {} plies", index + 1, record.players[0], record.players[1], record.moves.len());
+        let analysis = analyze_game(record, engine.as_mut(), &limits, |ply, last| {
+            eprint!("\r  evaluating ply {}/{}", ply, last);
+            let _ = io::stderr().flush();
+        });
+        eprintln!();
+        let analysis = match analysis {
+            Ok(analysis) => analysis,
+            Err(e) => {
+                eprintln!("  skipped: {}", e);
+                skipped += 1;
+                continue;
+            }
+        };
+        if !quiet {
+            for mov in &analysis.analysis {
+                println!("  {}", mov);
+            }
+        }
+        for summary in &analysis.summary {
+            println!("  {}", summary);
+        }
+        if let Some(file) = out.as_mut() {
+            writeln!(file, "{}", analysis.to_json_line()).map_err(|e| format!("Could not write the analysis: {}", e))?;
+        }
+    }
+    if skipped > 0 {
+        return Err(format!("{} game(s) could not be analyzed", skipped));
+    }
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use quorridor::{engine_config::EngineConfig, game_record::{play_game, append_record, random_opening}, elo::{MatchScore, Sprt, SprtDecision}, time_control::GameClock};
+
+fn print_usage() {
+    println!("Usage: arena --a SPEC --b SPEC [--games N] [--parallel N] [--max-plies N] [--random-opening PLIES] [--seed N] [--records FILE] [--clock SECS] [--inc SECS] [--sprt ELO0,ELO1] [--alpha A] [--beta B]");
+    println!("  SPEC is kind[:key=value,...], e.g. mcts:playouts=2000,c=0.7 or network:net=ckpt.safetensors,playouts=800");
+    println!("  kinds: mcts, rollout, network, alphabeta, random, shortest, walls, oneply");
+    println!("  --clock gives each side SECS per game plus --inc per move; running out loses the game");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+// The value after `flag`, if given, parsed as T
+fn parse_arg<T: FromStr>(args: &[String], flag: &str, what: &str) -> Result<Option<T>, String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.parse().map_err(|_| format!("{} takes {}, got '{}'", flag, what, v)))
+        .transpose()
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let arg = |flag: &str| args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let number = |flag: &str, default: usize| parse_arg(args, flag, "a whole number").map(|n| n.unwrap_or(default));
+    let seconds = |flag: &str| -> Result<Option<Duration>, String> {
+        parse_arg::<f64>(args, flag, "a number of seconds")?
+            .map(|secs| Duration::try_from_secs_f64(secs).map_err(|_| format!("{} takes a number of seconds, got {}", flag, secs)))
+            .transpose()
+    };
+    // SPRT error rates; 0, 1 and beyond give infinite bounds the test never reaches
+    let probability = |flag: &str| -> Result<f64, String> {
+        let p = parse_arg::<f64>(args, flag, "a probability")?.unwrap_or(0.05);
+        if p > 0.0 && p < 1.0 { Ok(p) } else { Err(format!("{} takes a probability strictly between 0 and 1, got {}", flag, p)) }
+    };
+
+    let (Some(spec_a), Some(spec_b)) = (arg("--a"), arg("--b")) else {
+        print_usage();
+        return Ok(());
+    };
+    let configs = [EngineConfig::parse(&spec_a)?, EngineConfig::parse(&spec_b)?];
+    // Fail before starting any games if an engine cannot be built, e.g. a missing checkpoint
+    for config in &configs {
+        config.build().map_err(|e| format!("{}: {}", config.spec, e))?;
+    }
+
+    let num_games = number("--games", 100)?;
+    let parallel = number("--parallel", 4)?.max(1);
+    let max_plies = number("--max-plies", 200)?;
+    let opening_plies = number("--random-opening", 4)?;
+    let seed = number("--seed", 0)? as u64;
+    let records_path = arg("--records");
+    let clock = match seconds("--clock")? {
+        Some(total) => Some(GameClock::new(total, seconds("--inc")?.unwrap_or(Duration::ZERO))),
+        None => None,
+    };
+    let sprt = match arg("--sprt") {
+        Some(bounds) => {
+            let (elo0, elo1) = bounds.split_once(',')
+                .and_then(|(elo0, elo1)| Some((elo0.trim().parse().ok()?, elo1.trim().parse().ok()?)))
+                .filter(|(elo0, elo1): &(f64, f64)| elo0.is_finite() && elo1.is_finite())
+                .ok_or_else(|| format!("--sprt takes ELO0,ELO1, e.g. 0,5; got '{}'", bounds))?;
+            Some(Sprt {
+                alpha: probability("--alpha")?,
+                beta: probability("--beta")?,
+                ..Sprt::new(elo0, elo1)
+            })
+        }
+        None => None,
+    };
+
+    println!("=== Quorridor Arena ===");
+    println!("  A: {}", spec_a);
+    println!("  B: {}", spec_b);
+    println!("  {} games, {} in parallel, {} random opening plies, draw after {} plies", num_games, parallel, opening_plies, max_plies);
+    if let Some(clock) = &clock {
+        println!("  Clock: {:.0}s + {:.1}s per move", clock.remaining(0).as_secs_f64(), clock.state(0).increment.as_secs_f64());
+    }
+    if let Some(sprt) = &sprt {
+        let (lower, upper) = sprt.bounds();
+        println!("  SPRT elo0={} elo1={} alpha={} beta={} (LLR bounds {:.2}, {:.2})", sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta, lower, upper);
+    }
+    println!();
+
+    let configs = Arc::new(configs);
+    let next_game = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+
+    // Engines are not Send, so every worker builds its own pair
+    let workers: Vec<_> = (0..parallel).map(|_| {
+        let (configs, next_game, stop, tx, clock) = (configs.clone(), next_game.clone(), stop.clone(), tx.clone(), clock.clone());
+        thread::spawn(move || {
+            let mut a = configs[0].build().unwrap();
+            let mut b = configs[1].build().unwrap();
+            let (limits_a, limits_b) = (configs[0].limits(), configs[1].limits());
+            loop {
+                let game = next_game.fetch_add(1, Ordering::Relaxed);
+                if game >= num_games || stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let opening = random_opening(seed.wrapping_add((game / 2) as u64), opening_plies);
+                let a_first = game % 2 == 0;
+                let mut record = if a_first {
+                    play_game([a.as_mut(), b.as_mut()], [&limits_a, &limits_b], &opening, max_plies, clock.clone())
+                } else {
+                    play_game([b.as_mut(), a.as_mut()], [&limits_b, &limits_a], &opening, max_plies, clock.clone())
+                };
+                record.players = if a_first {
+                    [configs[0].spec.clone(), configs[1].spec.clone()]
+                } else {
+                    [configs[1].spec.clone(), configs[0].spec.clone()]
+                };
+                if tx.send((game, a_first, record)).is_err() {
+                    break;
+                }
+            }
+        })
+    }).collect();
+    drop(tx);
+
+    let mut score = MatchScore::default();
+    let mut decision = SprtDecision::Continue;
+    for (game, a_first, record) in rx {
+        let a_seat = if a_first { 0 } else { 1 };
+        score.record(record.winner.map(|w| w == a_seat));
+
+        let outcome = match record.winner {
+            Some(w) if w == a_seat => "A wins",
+            Some(_) => "B wins",
+            None => "draw",
+        };
+        let (lo, hi) = score.elo_interval();
+        println!("Game {:>4}: A as player {}, {} in {} plies ({:?}) | +{} ={} -{} | Elo {:+.0} [{:+.0}, {:+.0}]",
+            game + 1, a_seat, outcome, record.moves.len(), record.termination,
+            score.wins, score.draws, score.losses, score.elo(), lo, hi);
+
+        if let Some(path) = &records_path
+            && let Err(e) = append_record(path, &record) {
+            eprintln!("Failed to write {}: {}", path, e);
+        }
+
+        if let Some(sprt) = &sprt {
+            decision = sprt.decide(&score);
+            if decision != SprtDecision::Continue {
+                stop.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+    }
+
+    // Games still running when SPRT stopped the match are not counted
+    stop.store(true, Ordering::Relaxed);
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let (lo, hi) = score.elo_interval();
+    println!();
+    println!("=== Result ===");
+    println!("  A: {}", spec_a);
+    println!("  B: {}", spec_b);
+    println!("  Games: {}  W/D/L for A: {}/{}/{}  score {:.1}%", score.games(), score.wins, score.draws, score.losses, score.score() * 100.0);
+    println!("  Elo difference (A - B): {:+.1}  95% CI [{:+.1}, {:+.1}]", score.elo(), lo, hi);
+    if let Some(sprt) = &sprt {
+        let verdict = match decision {
+            SprtDecision::AcceptH1 => "H1 accepted: A is stronger (PASS)",
+            SprtDecision::AcceptH0 => "H0 accepted: A is not stronger (FAIL)",
+            SprtDecision::Continue => "inconclusive",
+        };
+        println!("  SPRT: LLR {:.2}, {}", score.llr(sprt.elo0, sprt.elo1), verdict);
+    }
+    Ok(())
+}
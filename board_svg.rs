@@ -0,0 +1,343 @@
+// Vector pictures of positions for docs and bug reports: a Quorridor as SVG
+// with optional arrows, highlighted walls and the path overlay, rasterized to
+// PNG with resvg, and whole game records as animated SVG or GIF.
+use mcts::GameState;
+use resvg::{tiny_skia, usvg};
+use std::fmt::Write;
+
+use crate::game_record::GameRecord;
+use crate::moves::Move;
+use crate::path_analysis::path_overlay;
+use crate::quorridor::{Quorridor, GRID_HEIGHT, GRID_WIDTH, move_delta};
+use crate::wall::Orientation;
+
+const SQUARE: f64 = 48.0;
+const GUTTER: f64 = 12.0;  // Wall lane between squares
+const MARGIN: f64 = 28.0;  // Room for the coordinate labels
+
+const PLAYER_COLORS: [&str; 2] = ["#2a9d8f", "#9b5de5"];
+const WALL_COLOR: &str = "#8d5a2b";
+pub const HIGHLIGHT_COLOR: &str = "#e76f51";
+const CRITICAL_COLOR: &str = "#d62828";
+
+// Labels are rasterized with this font rather than whatever the host has
+const LABEL_FONT: &[u8] = include_bytes!("fonts/DejaVuSans.ttf");
+const LABEL_FONT_FAMILY: &str = "DejaVu Sans";
+
+// Plies shown per second in animations, and how many frames the final position is held
+pub const DEFAULT_FRAME_MS: u32 = 700;
+const FINAL_HOLD_FRAMES: u32 = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arrow {
+    pub from: (i64, i64),  // Grid coordinates of pawn squares
+    pub to: (i64, i64),
+    pub color: String,
+}
+
+// Extras drawn over the position
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotations {
+    pub arrows: Vec<Arrow>,
+    pub walls: Vec<(i64, i64, Orientation)>,  // Drawn in the highlight color, placed or not
+    pub paths: bool,  // Shortest routes and critical edges
+    pub caption: Option<String>,
+}
+
+impl Annotations {
+    // Mark `mov` as played by the side to move in `state`: an arrow for a pawn
+    // move, a highlighted wall for a wall
+    pub fn add_move(&mut self, state: &Quorridor, mov: &Move, color: &str) {
+        match mov {
+            Move::PlaceWall(x, y, orientation) => self.walls.push((*x, *y, *orientation)),
+            _ => {
+                let piece = state.player_pieces[state.active_player];
+                let (dx, dy) = move_delta(mov).unwrap();
+                self.arrows.push(Arrow { from: (piece.x, piece.y), to: (piece.x + dx, piece.y + dy), color: color.to_string() });
+            }
+        }
+    }
+}
+
+// Pixel position of a grid coordinate along one axis: odd coordinates are
+// square centers, even ones the middle of a wall lane
+fn axis(coordinate: i64) -> f64 {
+    if coordinate % 2 == 1 {
+        MARGIN + ((coordinate - 1) / 2) as f64 * (SQUARE + GUTTER) + SQUARE / 2.0
+    } else {
+        MARGIN + (coordinate / 2) as f64 * (SQUARE + GUTTER) - GUTTER / 2.0
+    }
+}
+
+fn px(x: i64) -> f64 {
+    axis(x)
+}
+
+// Rows are flipped so player 0 moves up the picture
+fn py(y: i64) -> f64 {
+    axis(GRID_HEIGHT as i64 - 1 - y)
+}
+
+fn board_size() -> (f64, f64) {
+    let squares = ((GRID_WIDTH - 1) / 2) as f64;
+    let rows = ((GRID_HEIGHT - 1) / 2) as f64;
+    (2.0 * MARGIN + squares * SQUARE + (squares - 1.0) * GUTTER, 2.0 * MARGIN + rows * SQUARE + (rows - 1.0) * GUTTER)
+}
+
+fn caption_height(annotations: &Annotations) -> f64 {
+    if annotations.caption.is_some() { 24.0 } else { 0.0 }
+}
+
+fn wall_rect(x: i64, y: i64, orientation: Orientation, color: &str) -> String {
+    let (left, top, width, height) = match orientation {
+        Orientation::Horizontal => (px(x + 1) - SQUARE / 2.0, py(y) - GUTTER / 2.0 + 1.0, px(x + 3) - px(x + 1) + SQUARE, GUTTER - 2.0),
+        Orientation::Vertical => (px(x) - GUTTER / 2.0 + 1.0, py(y + 3) - SQUARE / 2.0, GUTTER - 2.0, py(y + 1) - py(y + 3) + SQUARE),
+    };
+    format!(r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="2" fill="{}"/>"#, left, top, width, height, color)
+}
+
+// The squares and labels, which never change during a game
+fn board_background() -> String {
+    let (width, height) = board_size();
+    let mut svg = format!(r##"<rect width="{:.0}" height="{:.0}" fill="#f4ecd8"/>"##, width, height);
+    for y in (1..GRID_HEIGHT as i64 - 1).step_by(2) {
+        for x in (1..GRID_WIDTH as i64 - 1).step_by(2) {
+            write!(svg, r##"<rect x="{:.1}" y="{:.1}" width="{:.0}" height="{:.0}" rx="3" fill="#d9c7a1"/>"##,
+                px(x) - SQUARE / 2.0, py(y) - SQUARE / 2.0, SQUARE, SQUARE).unwrap();
+        }
+    }
+    for x in (1..GRID_WIDTH as i64 - 1).step_by(2) {
+        let letter = (b'a' + (x / 2) as u8) as char;
+        write!(svg, r##"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="14" text-anchor="middle" fill="#555">{}</text>"##,
+            px(x), height - MARGIN / 2.0 + 5.0, letter).unwrap();
+    }
+    for y in (1..GRID_HEIGHT as i64 - 1).step_by(2) {
+        write!(svg, r##"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="14" text-anchor="middle" fill="#555">{}</text>"##,
+            MARGIN / 2.0, py(y) + 5.0, (y - 1) / 2 + 1).unwrap();
+    }
+    svg
+}
+
+// Walls, pawns and annotations of one position
+fn position_layer(game: &Quorridor, annotations: &Annotations) -> String {
+    let mut svg = String::new();
+    if annotations.paths {
+        let overlay = path_overlay(game);
+        for (player, route) in overlay.routes.iter().enumerate() {
+            // Offset the two routes a little so a shared stretch shows both
+            let shift = if player == 0 { -4.0 } else { 4.0 };
+            let points: Vec<String> = route.iter().map(|&(x, y)| format!("{:.1},{:.1}", px(x) + shift, py(y) + shift)).collect();
+            write!(svg, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="3" stroke-dasharray="6 4" opacity="0.8"/>"#,
+                points.join(" "), PLAYER_COLORS[player]).unwrap();
+        }
+        for &(x, y) in overlay.critical.iter().flatten() {
+            // A short bar across the edge between the two squares
+            let (x1, y1, x2, y2) = if x % 2 == 0 {
+                (px(x), py(y) - SQUARE / 3.0, px(x), py(y) + SQUARE / 3.0)
+            } else {
+                (px(x) - SQUARE / 3.0, py(y), px(x) + SQUARE / 3.0, py(y))
+            };
+            write!(svg, r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="3" stroke-linecap="round"/>"#,
+                x1, y1, x2, y2, CRITICAL_COLOR).unwrap();
+        }
+    }
+
+    // Placed walls, found from the grid: a wall's middle cell is always a wall
+    // lane crossing with walled cells on both sides along the wall
+    for y in (0..GRID_HEIGHT as i64).step_by(2) {
+        for x in (0..GRID_WIDTH as i64).step_by(2) {
+            let wall_at = |x: i64, y: i64| {
+                (0..GRID_WIDTH as i64).contains(&x) && (0..GRID_HEIGHT as i64).contains(&y) && game.grid[y as usize][x as usize]
+            };
+            if !wall_at(x, y) {
+                continue;
+            }
+            if wall_at(x - 1, y) && wall_at(x + 1, y) {
+                svg += &wall_rect(x - 2, y, Orientation::Horizontal, WALL_COLOR);
+            }
+            if wall_at(x, y - 1) && wall_at(x, y + 1) {
+                svg += &wall_rect(x, y - 2, Orientation::Vertical, WALL_COLOR);
+            }
+        }
+    }
+    for &(x, y, orientation) in &annotations.walls {
+        svg += &wall_rect(x, y, orientation, HIGHLIGHT_COLOR);
+    }
+
+    for (player, piece) in game.player_pieces.iter().enumerate() {
+        write!(svg, r##"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}" stroke="#222" stroke-width="2"/>"##,
+            px(piece.x), py(piece.y), SQUARE / 2.0 - 8.0, PLAYER_COLORS[player]).unwrap();
+        write!(svg, r##"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="16" font-weight="bold" text-anchor="middle" fill="#fff">{}</text>"##,
+            px(piece.x), py(piece.y) + 6.0, player).unwrap();
+    }
+
+    for arrow in &annotations.arrows {
+        let (x1, y1, x2, y2) = (px(arrow.from.0), py(arrow.from.1), px(arrow.to.0), py(arrow.to.1));
+        // Stop short of the target center so the head sits inside the square
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt().max(1.0);
+        let (ux, uy) = ((x2 - x1) / length, (y2 - y1) / length);
+        let (tip_x, tip_y) = (x2 - ux * 8.0, y2 - uy * 8.0);
+        let (base_x, base_y) = (tip_x - ux * 12.0, tip_y - uy * 12.0);
+        write!(svg, r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="5" stroke-linecap="round" opacity="0.85"/>"#,
+            x1, y1, base_x, base_y, arrow.color).unwrap();
+        write!(svg, r#"<polygon points="{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}" fill="{}" opacity="0.85"/>"#,
+            tip_x, tip_y, base_x - uy * 8.0, base_y + ux * 8.0, base_x + uy * 8.0, base_y - ux * 8.0, arrow.color).unwrap();
+    }
+
+    if let Some(caption) = &annotations.caption {
+        let (width, height) = board_size();
+        write!(svg, r##"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="16" text-anchor="middle" fill="#222">{}</text>"##,
+            width / 2.0, height + 16.0, escape(caption)).unwrap();
+    }
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn document(height_extra: f64, body: &str) -> String {
+    let (width, height) = board_size();
+    format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}">{}</svg>"#,
+        width, height + height_extra, width, height + height_extra, body)
+}
+
+pub fn position_svg(game: &Quorridor, annotations: &Annotations) -> String {
+    let extra = caption_height(annotations);
+    let background = if extra > 0.0 {
+        let (width, height) = board_size();
+        format!(r##"<rect width="{:.0}" height="{:.0}" fill="#f4ecd8"/>{}"##, width, height + extra, board_background())
+    } else {
+        board_background()
+    };
+    document(extra, &(background + &position_layer(game, annotations)))
+}
+
+// Rasterize an SVG at `scale` times its size. Sans-serif labels use the
+// embedded font, so pictures come out the same on any host.
+pub fn svg_to_pixmap(svg: &str, scale: f32) -> Result<tiny_skia::Pixmap, String> {
+    let mut options = usvg::Options::default();
+    let fonts = options.fontdb_mut();
+    fonts.load_font_data(LABEL_FONT.to_vec());
+    fonts.set_sans_serif_family(LABEL_FONT_FAMILY);
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| format!("invalid SVG: {}", e))?;
+    let size = tree.size().to_int_size().scale_by(scale).ok_or("image too large")?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("image too large")?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+pub fn svg_to_png(svg: &str, scale: f32) -> Result<Vec<u8>, String> {
+    svg_to_pixmap(svg, scale)?.encode_png().map_err(|e| e.to_string())
+}
+
+// Every position of a record with the move that led to it marked
+fn record_frames(record: &GameRecord, paths: bool) -> Result<Vec<(Quorridor, Annotations)>, String> {
+    let mut state = Quorridor::default();
+    let mut frames = vec![(state.clone(), Annotations { paths, caption: Some(format!("{} vs {}", record.players[0], record.players[1])), ..Default::default() })];
+    for (ply, (mov, text)) in record.parse_moves()?.iter().zip(&record.moves).enumerate() {
+        let mut annotations = Annotations { paths, caption: Some(format!("{}. {}", ply + 1, text)), ..Default::default() };
+        annotations.add_move(&state, mov, HIGHLIGHT_COLOR);
+        state.make_move(mov);
+        frames.push((state.clone(), annotations));
+    }
+    if let Some((_, last)) = frames.last_mut() {
+        let result = match record.winner {
+            Some(winner) => format!("player {} wins", winner),
+            None => "draw".to_string(),
+        };
+        last.caption = Some(format!("{}, {}", last.caption.take().unwrap_or_default(), result));
+    }
+    Ok(frames)
+}
+
+// The record as one SVG that steps through its positions every `frame_ms`
+// and loops, using SMIL animation so it plays in any browser
+pub fn record_animated_svg(record: &GameRecord, frame_ms: u32, paths: bool) -> Result<String, String> {
+    let frames = record_frames(record, paths)?;
+    let durations: Vec<u32> = (0..frames.len()).map(|i| if i + 1 == frames.len() { FINAL_HOLD_FRAMES } else { 1 }).collect();
+    let total: u32 = durations.iter().sum();
+    let mut body = format!(r##"<rect width="100%" height="100%" fill="#f4ecd8"/>{}"##, board_background());
+    let mut elapsed = 0;
+    for ((state, annotations), duration) in frames.iter().zip(&durations) {
+        let start = elapsed as f64 / total as f64;
+        let end = (elapsed + duration) as f64 / total as f64;
+        // Discrete opacity steps: visible from `start` to `end` of every loop
+        let (values, key_times) = match (elapsed == 0, elapsed + duration == total) {
+            (true, true) => ("1".to_string(), "0".to_string()),
+            (true, false) => ("1;0".to_string(), format!("0;{:.4}", end)),
+            (false, true) => ("0;1".to_string(), format!("0;{:.4}", start)),
+            (false, false) => ("0;1;0".to_string(), format!("0;{:.4};{:.4}", start, end)),
+        };
+        write!(body, r#"<g opacity="{}"><animate attributeName="opacity" values="{}" keyTimes="{}" dur="{:.3}s" calcMode="discrete" repeatCount="indefinite"/>{}</g>"#,
+            if elapsed == 0 { 1 } else { 0 }, values, key_times, (total * frame_ms) as f64 / 1000.0, position_layer(state, annotations)).unwrap();
+        elapsed += duration;
+    }
+    Ok(document(24.0, &body))
+}
+
+// The record as a looping GIF, each position rasterized at `scale`
+pub fn record_gif(record: &GameRecord, frame_ms: u32, scale: f32, paths: bool) -> Result<Vec<u8>, String> {
+    let frames = record_frames(record, paths)?;
+    let pixmaps = frames.iter()
+        .map(|(state, annotations)| svg_to_pixmap(&position_svg(state, annotations), scale))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (width, height) = (pixmaps[0].width() as u16, pixmaps[0].height() as u16);
+    let mut out = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut out, width, height, &[]).map_err(|e| e.to_string())?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+        let count = pixmaps.len();
+        for (i, pixmap) in pixmaps.into_iter().enumerate() {
+            // The background is opaque, so premultiplied pixels are plain RGBA
+            let mut pixels = pixmap.take();
+            let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+            let hold = if i + 1 == count { FINAL_HOLD_FRAMES } else { 1 };
+            frame.delay = (frame_ms * hold / 10) as u16;
+            encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_record::test_record;
+
+    #[test]
+    fn test_position_svg_draws_walls_and_annotations() {
+        let mut game = Quorridor::default();
+        game.make_move(&Move::PlaceWall(8, 8, Orientation::Horizontal));
+        let mut annotations = Annotations { paths: true, ..Default::default() };
+        annotations.add_move(&game, &Move::Up, "#000");
+        annotations.add_move(&game, &Move::PlaceWall(2, 2, Orientation::Vertical), "#000");
+        let svg = position_svg(&game, &annotations);
+        assert_eq!(svg.matches(WALL_COLOR).count(), 1);
+        assert_eq!(svg.matches(HIGHLIGHT_COLOR).count(), 1);
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert_eq!(svg.matches("<polygon").count(), 1);
+
+        let png = svg_to_png(&svg, 0.5).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+
+    #[test]
+    fn test_labels_rasterize_with_the_embedded_font() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20"><text x="2" y="16" font-family="sans-serif" font-size="16" fill="#000">e2</text></svg>"##;
+        let pixmap = svg_to_pixmap(svg, 1.0).unwrap();
+        assert!(pixmap.pixels().iter().any(|pixel| pixel.alpha() > 0));
+    }
+
+    #[test]
+    fn test_record_animations() {
+        let record = test_record(&["e2", "e8", "e3h"]);
+        let svg = record_animated_svg(&record, DEFAULT_FRAME_MS, false).unwrap();
+        assert_eq!(svg.matches("<animate ").count(), 4);
+        let gif = record_gif(&record, DEFAULT_FRAME_MS, 0.25, false).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+
+        let broken = GameRecord { moves: vec!["e5".to_string()], ..record };
+        assert!(record_animated_svg(&broken, DEFAULT_FRAME_MS, false).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use mcts::GameState;
+use std::env;
+use std::str::FromStr;
+
+use quorridor::{Quorridor, game_record::{GameRecord, load_records}, notation::move_to_notation, opening_book::{OpeningBook, DEFAULT_MIN_GAMES}, ratings::RatingsDb};
+
+fn print_usage() {
+    println!("Usage: book --out FILE [--records FILE[,FILE...]] [--db FILE] [--max-ply N] [--min-games N]");
+    println!("  Builds an opening book from arena/selfplay record files and tournament databases.");
+    println!("  Use it with --book FILE in quorridor and selfplay, or book=FILE in an engine spec.");
+}
+
+// The book's main line: the most played move at each ply, as far as the book goes
+fn print_main_line(book: &OpeningBook, min_games: u32) {
+    let mut state = Quorridor::default();
+    let mut line = Vec::new();
+    while line.len() < book.max_ply && !state.game_over() {
+        let moves = book.lookup(&state, min_games);
+        let Some((mov, entry)) = moves.first() else { break };
+        line.push(format!("{} ({} games, {:.0}%)", entry.notation, entry.games, entry.score() * 100.0));
+        state.make_move(mov);
+    }
+    println!("Main line: {}", if line.is_empty() { "(empty)".to_string() } else { line.join(" ") });
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+// The value after `flag`, if given, parsed as T
+fn parse_arg<T: FromStr>(args: &[String], flag: &str, what: &str) -> Result<Option<T>, String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.parse().map_err(|_| format!("{} takes {}, got '{}'", flag, what, v)))
+        .transpose()
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let arg = |flag: &str| args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+
+    let Some(out) = arg("--out") else {
+        print_usage();
+        return Ok(());
+    };
+    let max_ply = parse_arg(args, "--max-ply", "a whole number")?.unwrap_or(12);
+    let min_games = parse_arg(args, "--min-games", "a whole number")?.unwrap_or(DEFAULT_MIN_GAMES);
+
+    let mut records: Vec<GameRecord> = Vec::new();
+    for path in arg("--records").iter().flat_map(|list| list.split(',')) {
+        match load_records(path) {
+            Ok(loaded) => {
+                println!("{}: {} games", path, loaded.len());
+                records.extend(loaded);
+            }
+            Err(e) => eprintln!("Skipping {}", e),
+        }
+    }
+    if let Some(path) = arg("--db") {
+        match RatingsDb::open(&path).and_then(|db| db.records()) {
+            Ok(loaded) => {
+                println!("{}: {} games", path, loaded.len());
+                records.extend(loaded);
+            }
+            Err(e) => eprintln!("Skipping {}: {}", path, e),
+        }
+    }
+    if records.is_empty() {
+        return Err("No games to build a book from".to_string());
+    }
+
+    let mut book = OpeningBook::new(max_ply);
+    let mut skipped = 0;
+    for record in &records {
+        if let Err(e) = book.add_record(record) {
+            eprintln!("Skipping game: {}", e);
+            skipped += 1;
+        }
+    }
+
+    println!("Book: {} positions from {} games (first {} plies), {} skipped", book.positions.len(), records.len() - skipped, max_ply, skipped);
+    let start = Quorridor::default();
+    for (mov, entry) in book.lookup(&start, min_games).iter().take(5) {
+        println!("  {:<5} {:>6} games  {:>5.1}%", move_to_notation(&start, mov), entry.games, entry.score() * 100.0);
+    }
+    print_main_line(&book, min_games);
+
+    book.save(&out).map_err(|e| format!("Failed to save book: {}", e))?;
+    println!("Saved to {}", out);
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use mcts::GameState;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+use crate::engine::{Engine, position_after};
+use crate::moves::Move;
+use crate::quorridor::{Quorridor, shortest_path_to_goal};
+use crate::search_result::SearchResult;
+use crate::time_control::SearchLimits;
+
+// Fixed reference players for measuring search changes against, weakest first
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BaselineKind {
+    Random,        // Uniformly random legal move
+    ShortestPath,  // Always step along the shortest path, never place walls
+    GreedyWalls,   // Place the wall that most increases opponent path minus own path
+    OnePly,        // Best path difference after any single move
+}
+
+pub const BASELINE_LADDER: [BaselineKind; 4] =
+    [BaselineKind::Random, BaselineKind::ShortestPath, BaselineKind::GreedyWalls, BaselineKind::OnePly];
+
+impl BaselineKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BaselineKind::Random => "random",
+            BaselineKind::ShortestPath => "shortest",
+            BaselineKind::GreedyWalls => "walls",
+            BaselineKind::OnePly => "oneply",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        BASELINE_LADDER.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+// Opponent's shortest path minus `player`'s
+fn path_difference(state: &Quorridor, player: usize) -> i64 {
+    match (shortest_path_to_goal(state, player), shortest_path_to_goal(state, 1 - player)) {
+        (Some(mine), Some(theirs)) => theirs as i64 - mine as i64,
+        _ => i64::MIN,
+    }
+}
+
+fn is_wall(mov: &Move) -> bool {
+    matches!(mov, Move::PlaceWall(..))
+}
+
+// The pawn move that leaves the shortest path to goal, never a wall
+pub fn shortest_path_step(state: &Quorridor) -> Option<Move> {
+    let player = state.active_player;
+    state.available_moves().into_iter()
+        .filter(|mov| !is_wall(mov))
+        .min_by_key(|mov| shortest_path_to_goal(&position_after(state, std::slice::from_ref(mov)), player).unwrap_or(usize::MAX))
+        // A pawn with no step left has to place a wall
+        .or_else(|| state.legal_moves().into_iter().next())
+}
+
+// The wall that most improves the path difference, or a shortest-path step if no wall helps
+pub fn greedy_wall(state: &Quorridor) -> Option<Move> {
+    let player = state.active_player;
+    let current = path_difference(state, player);
+    let best_wall = state.legal_moves().into_iter()
+        .filter(is_wall)
+        .map(|mov| (path_difference(&position_after(state, std::slice::from_ref(&mov)), player), mov))
+        .fold(None, |best: Option<(i64, Move)>, (score, mov)| match best {
+            Some((best_score, _)) if best_score >= score => best,
+            _ => Some((score, mov)),
+        });
+
+    match best_wall {
+        Some((score, mov)) if score > current => Some(mov),
+        _ => shortest_path_step(state),
+    }
+}
+
+// The move with the best path difference one ply ahead; pawn moves win ties
+pub fn one_ply_lookahead(state: &Quorridor) -> Option<Move> {
+    let player = state.active_player;
+    let mut best: Option<(i64, Move)> = None;
+    for mov in state.legal_moves() {
+        let child = position_after(state, std::slice::from_ref(&mov));
+        let score = if child.game_over() { i64::MAX } else { path_difference(&child, player) };
+        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, mov));
+        }
+    }
+    best.map(|(_, mov)| mov)
+}
+
+pub struct BaselineBot {
+    kind: BaselineKind,
+    rng: StdRng,
+    position: Quorridor,
+    stop: Arc<AtomicBool>,
+}
+
+impl BaselineBot {
+    pub fn new(kind: BaselineKind) -> Self {
+        BaselineBot::with_rng(kind, StdRng::from_entropy())
+    }
+
+    // Only the random bot draws from the rng; the others are deterministic
+    pub fn with_rng(kind: BaselineKind, rng: StdRng) -> Self {
+        BaselineBot { kind, rng, position: Quorridor::default(), stop: Arc::new(AtomicBool::new(false)) }
+    }
+
+    pub fn kind(&self) -> BaselineKind {
+        self.kind
+    }
+}
+
+impl Engine for BaselineBot {
+    fn name(&self) -> String {
+        self.kind.name().to_string()
+    }
+
+    fn new_game(&mut self) {
+        self.position = Quorridor::default();
+    }
+
+    fn set_position(&mut self, start: &Quorridor, moves: &[Move]) {
+        self.position = position_after(start, moves);
+    }
+
+    fn search(&mut self, _: &SearchLimits) -> SearchResult {
+        let started = Instant::now();
+        let mov = match self.kind {
+            BaselineKind::Random => self.position.legal_moves().choose(&mut self.rng).cloned(),
+            BaselineKind::ShortestPath => shortest_path_step(&self.position),
+            BaselineKind::GreedyWalls => greedy_wall(&self.position),
+            BaselineKind::OnePly => one_ply_lookahead(&self.position),
+        };
+        SearchResult::without_search(&self.position, mov, started.elapsed())
+    }
+
+    fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::Piece;
+
+    #[test]
+    fn test_names_round_trip() {
+        for kind in BASELINE_LADDER {
+            assert_eq!(BaselineKind::from_name(kind.name()), Some(kind));
+        }
+    }
+
+    #[test]
+    fn test_shortest_path_step_heads_for_goal() {
+        let game = Quorridor::default();
+        assert_eq!(shortest_path_step(&game), Some(Move::Down));
+    }
+
+    #[test]
+    fn test_greedy_wall_blocks_a_runner() {
+        // Player 1 is two steps from winning, player 0 far from theirs
+        let mut game = Quorridor {
+            player_pieces: [Piece { x: 1, y: 1 }, Piece { x: 9, y: 5 }],
+            ..Default::default()
+        };
+        game.recompute_hash();
+
+        let before = shortest_path_to_goal(&game, 1).unwrap();
+        let mov = greedy_wall(&game).unwrap();
+        assert!(is_wall(&mov));
+        let after = shortest_path_to_goal(&position_after(&game, &[mov]), 1).unwrap();
+        assert!(after > before);
+    }
+
+    #[test]
+    fn test_one_ply_takes_the_win() {
+        let mut game = Quorridor {
+            player_pieces: [Piece { x: 3, y: 15 }, Piece { x: 9, y: 9 }],
+            ..Default::default()
+        };
+        game.recompute_hash();
+        assert_eq!(one_ply_lookahead(&game), Some(Move::Down));
+    }
+}
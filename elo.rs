@@ -0,0 +1,144 @@
+// Match statistics between two players: Elo difference with a confidence
+// interval, and a sequential probability ratio test for quick pass/fail.
+
+// z-score of the reported confidence interval (95%)
+pub const CONFIDENCE_Z: f64 = 1.96;
+
+// Expected score of a player rated `elo` above its opponent
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// Elo difference that gives an expected score of `score`
+pub fn elo_from_score(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+// Wins, draws and losses from the first player's side
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn record(&mut self, result: Option<bool>) {
+        match result {
+            Some(true) => self.wins += 1,
+            Some(false) => self.losses += 1,
+            None => self.draws += 1,
+        }
+    }
+
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    // Variance of a single game's score around the mean
+    fn variance(&self) -> f64 {
+        let n = self.games() as f64;
+        let s = self.score();
+        (self.wins as f64 * (1.0 - s).powi(2) + self.draws as f64 * (0.5 - s).powi(2) + self.losses as f64 * s.powi(2)) / n
+    }
+
+    pub fn elo(&self) -> f64 {
+        elo_from_score(self.score())
+    }
+
+    // Confidence interval on the Elo difference, from the normal approximation of the score
+    pub fn elo_interval(&self) -> (f64, f64) {
+        if self.games() < 2 {
+            return (f64::NEG_INFINITY, f64::INFINITY);
+        }
+        let margin = CONFIDENCE_Z * (self.variance() / self.games() as f64).sqrt();
+        let s = self.score();
+        (elo_from_score(s - margin), elo_from_score(s + margin))
+    }
+
+    // Log-likelihood ratio of elo1 against elo0 under the normal approximation
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance = self.variance();
+        if self.games() < 2 || variance <= 0.0 {
+            return 0.0;
+        }
+        let (s0, s1) = (expected_score(elo0), expected_score(elo1));
+        self.games() as f64 * (s1 - s0) * (2.0 * self.score() - s0 - s1) / (2.0 * variance)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SprtDecision {
+    Continue,
+    AcceptH0,  // The patch is no better than elo0
+    AcceptH1,  // The patch gains at least elo1
+}
+
+// Sequential probability ratio test of H0: elo = elo0 against H1: elo = elo1
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,  // Chance of accepting H1 when H0 holds
+    pub beta: f64,   // Chance of accepting H0 when H1 holds
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Sprt { elo0, elo1, alpha: 0.05, beta: 0.05 }
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn decide(&self, score: &MatchScore) -> SprtDecision {
+        let llr = score.llr(self.elo0, self.elo1);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtDecision::AcceptH1
+        } else if llr <= lower {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elo_and_score_are_inverse() {
+        for elo in [-400.0, -50.0, 0.0, 120.0, 600.0] {
+            assert!((elo_from_score(expected_score(elo)) - elo).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_interval_contains_estimate_and_narrows() {
+        let small = MatchScore { wins: 6, draws: 0, losses: 4 };
+        let large = MatchScore { wins: 600, draws: 0, losses: 400 };
+        let (lo, hi) = small.elo_interval();
+        assert!(lo < small.elo() && small.elo() < hi);
+        let (lo_large, hi_large) = large.elo_interval();
+        assert!(hi_large - lo_large < hi - lo);
+    }
+
+    #[test]
+    fn test_sprt_decides_clear_results() {
+        let sprt = Sprt::new(0.0, 30.0);
+        assert_eq!(sprt.decide(&MatchScore { wins: 700, draws: 0, losses: 300 }), SprtDecision::AcceptH1);
+        assert_eq!(sprt.decide(&MatchScore { wins: 300, draws: 0, losses: 700 }), SprtDecision::AcceptH0);
+        assert_eq!(sprt.decide(&MatchScore { wins: 3, draws: 0, losses: 2 }), SprtDecision::Continue);
+    }
+}
@@ -0,0 +1,337 @@
+// Exact solver for the wall-less endgame. Once neither side can place a wall
+// the board is fixed and the game is a race between the two pawns, small
+// enough (81 x 81 squares x side to move) to solve completely by retrograde
+// analysis, jumps and pawn blocking included.
+use mcts::GameState;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use serde::Serialize;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::move_filter::WallFilter;
+use crate::quorridor::Quorridor;
+use crate::zobrist;
+
+const SIDE: usize = 9;
+const SQUARES: usize = SIDE * SIDE;
+const STATES: usize = SQUARES * SQUARES * 2;
+
+// Race tables kept for wall layouts seen recently
+const MAX_CACHED_TABLES: usize = 128;
+
+// Walls solve tries: those on a shortest path or next to a pawn. Every wall
+// tried is a new layout needing a race table, so trying all of them would
+// flush the cache on each call.
+const SOLVER_WALLS: WallFilter = WallFilter { radius: 0, sample_rate: 0.0 };
+
+// A proven result from the side to move's point of view, with the number of
+// plies until the winning pawn reaches its goal
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum Proven {
+    Win(u32),
+    Loss(u32),
+}
+
+impl Proven {
+    // The same result seen from the parent position
+    pub fn for_parent(self) -> Proven {
+        match self {
+            Proven::Win(plies) => Proven::Loss(plies + 1),
+            Proven::Loss(plies) => Proven::Win(plies + 1),
+        }
+    }
+
+    pub fn plies(self) -> u32 {
+        match self {
+            Proven::Win(plies) | Proven::Loss(plies) => plies,
+        }
+    }
+
+    // Moves the winner still has to make
+    pub fn mate_in(self) -> u32 {
+        self.plies().div_ceil(2)
+    }
+
+    pub fn winner(self, side_to_move: usize) -> usize {
+        match self {
+            Proven::Win(_) => side_to_move,
+            Proven::Loss(_) => 1 - side_to_move,
+        }
+    }
+}
+
+impl fmt::Display for Proven {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Proven::Win(_) => write!(f, "mate in {}", self.mate_in()),
+            Proven::Loss(_) => write!(f, "mated in {}", self.mate_in()),
+        }
+    }
+}
+
+fn square_index(x: i64, y: i64) -> usize {
+    ((y as usize - 1) / 2) * SIDE + (x as usize - 1) / 2
+}
+
+fn state_index(square0: usize, square1: usize, side: usize) -> usize {
+    (square0 * SQUARES + square1) * 2 + side
+}
+
+// Hash of the walls on the board alone, the part of a position that stays
+// fixed for the rest of a wall-less game
+pub fn wall_layout_hash(state: &Quorridor) -> u64 {
+    let mut hash = state.zobrist;
+    for (player, piece) in state.player_pieces.iter().enumerate() {
+        hash ^= zobrist::pawn_key(player, piece.x, piece.y);
+        hash ^= zobrist::walls_remaining_key(player, state.walls_remaining[player]);
+    }
+    if state.active_player == 1 {
+        hash ^= zobrist::side_to_move_key();
+    }
+    hash
+}
+
+// Every pawn placement and side to move for one wall layout, solved
+pub struct RaceTable {
+    results: Vec<Option<Proven>>,  // None: neither side can force a win
+}
+
+impl RaceTable {
+    // Solve the race on the walls of `layout`; its pawns and wall counts are ignored
+    pub fn solve(layout: &Quorridor) -> Self {
+        let mut template = layout.clone();
+        template.walls_remaining = [0, 0];
+
+        let mut results = vec![None; STATES];
+        let mut predecessors: Vec<Vec<u32>> = vec![Vec::new(); STATES];
+        let mut unresolved = vec![0u8; STATES];
+        let mut queue = VecDeque::new();
+
+        for square0 in 0..SQUARES {
+            for square1 in (0..SQUARES).filter(|&s| s != square0) {
+                for side in 0..2 {
+                    let index = state_index(square0, square1, side);
+                    let mut state = template.clone();
+                    state.player_pieces[0].x = (square0 % SIDE) as i64 * 2 + 1;
+                    state.player_pieces[0].y = (square0 / SIDE) as i64 * 2 + 1;
+                    state.player_pieces[1].x = (square1 % SIDE) as i64 * 2 + 1;
+                    state.player_pieces[1].y = (square1 / SIDE) as i64 * 2 + 1;
+                    state.active_player = side;
+
+                    // The side to move has lost if the opponent's pawn is home or
+                    // it is stuck with nowhere to step
+                    let moves = state.available_moves();
+                    if state.game_over() || moves.is_empty() {
+                        results[index] = Some(if state.winner() == Some(side) { Proven::Win(0) } else { Proven::Loss(0) });
+                        queue.push_back(index);
+                        continue;
+                    }
+                    unresolved[index] = moves.len() as u8;
+                    for mov in &moves {
+                        let mut child = state.clone();
+                        child.make_move(mov);
+                        let pieces = child.player_pieces;
+                        let child_index = state_index(square_index(pieces[0].x, pieces[0].y), square_index(pieces[1].x, pieces[1].y), 1 - side);
+                        predecessors[child_index].push(index as u32);
+                    }
+                }
+            }
+        }
+
+        // Results are found in order of increasing length, so each win is the
+        // fastest and each loss the slowest available
+        while let Some(index) = queue.pop_front() {
+            let result = results[index].unwrap().for_parent();
+            for &parent in &predecessors[index] {
+                let parent = parent as usize;
+                if results[parent].is_some() {
+                    continue;
+                }
+                match result {
+                    Proven::Win(_) => {
+                        results[parent] = Some(result);
+                        queue.push_back(parent);
+                    }
+                    Proven::Loss(_) => {
+                        unresolved[parent] -= 1;
+                        if unresolved[parent] == 0 {
+                            results[parent] = Some(result);
+                            queue.push_back(parent);
+                        }
+                    }
+                }
+            }
+        }
+        RaceTable { results }
+    }
+
+    pub fn probe(&self, state: &Quorridor) -> Option<Proven> {
+        let pieces = state.player_pieces;
+        self.results[state_index(square_index(pieces[0].x, pieces[0].y), square_index(pieces[1].x, pieces[1].y), state.active_player)]
+    }
+}
+
+// Proves wins and losses late in the game: exactly once both sides are out of
+// walls, and with a short search while only one side has walls left. MCTS only
+// looks up wall-less races, since the search needs a table for every wall it tries.
+pub struct EndgameSolver {
+    tables: Mutex<HashMap<u64, Arc<RaceTable>>>,
+}
+
+impl Default for EndgameSolver {
+    fn default() -> Self {
+        EndgameSolver::new()
+    }
+}
+
+impl EndgameSolver {
+    pub fn new() -> Self {
+        EndgameSolver { tables: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn race_table(&self, state: &Quorridor) -> Arc<RaceTable> {
+        let key = wall_layout_hash(state);
+        if let Some(table) = self.tables.lock().unwrap().get(&key) {
+            return table.clone();
+        }
+        // Solved outside the lock; two threads may both solve a new layout
+        let table = Arc::new(RaceTable::solve(state));
+        let mut tables = self.tables.lock().unwrap();
+        if tables.len() >= MAX_CACHED_TABLES {
+            tables.clear();
+        }
+        tables.insert(key, table.clone());
+        table
+    }
+
+    // Result without searching: terminal positions, wall-less races, and
+    // races the side holding walls wins even without using them. In that last
+    // case the walls only add options, so the win stands, but a faster one may
+    // exist than the plies given.
+    pub fn probe(&self, state: &Quorridor) -> Option<Proven> {
+        if let Some(winner) = state.winner() {
+            return Some(if winner == state.active_player { Proven::Win(0) } else { Proven::Loss(0) });
+        }
+        let walls = state.walls_remaining;
+        if walls[0] > 0 && walls[1] > 0 {
+            return None;
+        }
+        let race = self.race_table(state).probe(state);
+        if walls == [0, 0] {
+            return race;
+        }
+        let holder = if walls[0] > 0 { 0 } else { 1 };
+        race.filter(|result| result.winner(state.active_player) == holder)
+    }
+
+    // Prove the position within `depth` plies of search on top of probe.
+    // Positions where both sides still have walls are never searched. Walls
+    // SOLVER_WALLS leaves out might still save a game, so a side that had
+    // some skipped is never proven lost.
+    pub fn solve(&self, state: &Quorridor, depth: u32) -> Option<Proven> {
+        if let Some(result) = self.probe(state) {
+            return Some(result);
+        }
+        if depth == 0 || (state.walls_remaining[0] > 0 && state.walls_remaining[1] > 0) {
+            return None;
+        }
+
+        let mut fastest_win: Option<u32> = None;
+        let mut slowest_loss = 0;
+        let moves = state.legal_moves();
+        let keep = SOLVER_WALLS.keep_mask(state, &moves, &mut rand::thread_rng());
+        let mut all_lost = keep.iter().all(|&k| k);
+        for (mov, _) in moves.iter().zip(keep).filter(|(_, k)| *k) {
+            let mut child = state.clone();
+            child.make_move(mov);
+            match self.solve(&child, depth - 1).map(Proven::for_parent) {
+                Some(Proven::Win(plies)) => {
+                    fastest_win = Some(fastest_win.map_or(plies, |best| best.min(plies)));
+                    if plies == 1 {
+                        break;
+                    }
+                }
+                Some(Proven::Loss(plies)) => slowest_loss = slowest_loss.max(plies),
+                None => all_lost = false,
+            }
+        }
+        match fastest_win {
+            Some(plies) => Some(Proven::Win(plies)),
+            None if all_lost => Some(Proven::Loss(slowest_loss)),
+            None => None,
+        }
+    }
+}
+
+// A wall-less race with the pawns at `p0` and `p1`, for tests of the modules playing out endgames
+#[cfg(test)]
+pub(crate) fn test_race(p0: (i64, i64), p1: (i64, i64), active_player: usize) -> Quorridor {
+    use crate::piece::Piece;
+    let mut game = Quorridor {
+        player_pieces: [Piece { x: p0.0, y: p0.1 }, Piece { x: p1.0, y: p1.1 }],
+        active_player,
+        walls_remaining: [0, 0],
+        ..Default::default()
+    };
+    game.recompute_hash();
+    game
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quorridor::shortest_path_to_goal;
+
+    #[test]
+    fn test_open_race_goes_to_the_shorter_path() {
+        let solver = EndgameSolver::new();
+        // Separate columns: player 0 needs 3 steps, player 1 needs 4
+        let game = test_race((1, 11), (17, 9), 0);
+        assert_eq!(shortest_path_to_goal(&game, 0), Some(3));
+        assert_eq!(solver.solve(&game, 0), Some(Proven::Win(5)));
+        assert_eq!(solver.solve(&game, 0).unwrap().mate_in(), 3);
+
+        // Player 1 to move needs 4 steps against 3 and loses
+        let game = test_race((1, 11), (17, 9), 1);
+        assert_eq!(solver.solve(&game, 0), Some(Proven::Loss(6)));
+    }
+
+    #[test]
+    fn test_jump_changes_the_race() {
+        let solver = EndgameSolver::new();
+        // Player 0 is two steps from goal with player 1 right in front, and
+        // jumps straight onto the goal row
+        let game = test_race((9, 13), (9, 15), 0);
+        assert_eq!(shortest_path_to_goal(&game, 0), Some(2));
+        assert_eq!(solver.solve(&game, 0), Some(Proven::Win(1)));
+    }
+
+    #[test]
+    fn test_walls_on_one_side_are_only_proven_when_they_cannot_lose() {
+        let solver = EndgameSolver::new();
+        let mut game = test_race((1, 11), (17, 9), 0);
+        game.walls_remaining = [3, 0];
+        game.recompute_hash();
+        assert_eq!(solver.probe(&game), Some(Proven::Win(5)));
+
+        // Player 1 wins the bare race but player 0 may still wall them off
+        let mut game = test_race((1, 3), (17, 5), 1);
+        game.walls_remaining = [1, 0];
+        game.recompute_hash();
+        assert_eq!(solver.probe(&game), None);
+    }
+
+    #[test]
+    fn test_solve_only_tries_walls_near_the_race() {
+        let solver = EndgameSolver::new();
+        let mut game = test_race((9, 3), (9, 15), 0);
+        game.walls_remaining = [1, 0];
+        game.recompute_hash();
+        assert_eq!(solver.probe(&game), None);
+        assert!(matches!(solver.solve(&game, 2), Some(Proven::Win(_))));
+
+        // One table per wall tried, well short of the 128 on an open board
+        assert!(solver.tables.lock().unwrap().len() < 32);
+    }
+}
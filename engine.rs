@@ -0,0 +1,307 @@
+use mcts::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::alphabeta::AlphaBeta;
+use crate::engine_config::EngineConfig;
+use crate::mcts_impl::{MyMCTS, MyEvaluator, PriorUCTPolicy};
+use crate::moves::Move;
+use crate::ponder::{Ponder, PonderStats};
+use crate::quorridor::Quorridor;
+use crate::search::run_search_until;
+use crate::search_result::SearchResult;
+use crate::time_control::{SearchLimits, DEFAULT_PLAYOUTS};
+use crate::transposition::{QuorridorTable, ReplacementPolicy};
+use crate::tree_reuse::TreeReuse;
+
+// A move-picking backend. Front-ends hand it the game so far with set_position
+// and ask for a move with search, so any engine can sit in any seat.
+pub trait Engine {
+    fn name(&self) -> String;
+
+    // Forget everything learned from the previous game
+    fn new_game(&mut self);
+
+    // The position reached by playing `moves` from `start`
+    fn set_position(&mut self, start: &Quorridor, moves: &[Move]);
+
+    fn search(&mut self, limits: &SearchLimits) -> SearchResult;
+
+    // Shared flag that makes a running search return early. Take it before
+    // calling search, since search holds the engine mutably. Search leaves
+    // the flag as it finds it; whoever sets it clears it before the next one.
+    fn stop_flag(&self) -> Arc<AtomicBool>;
+
+    fn stop(&self) {
+        self.stop_flag().store(true, Ordering::Relaxed);
+    }
+
+    // Think about the current position in the background until stop_ponder.
+    // Engines that cannot ponder ignore this.
+    fn start_ponder(&mut self) {}
+
+    fn stop_ponder(&mut self) -> Option<PonderStats> {
+        None
+    }
+}
+
+pub fn position_after(start: &Quorridor, moves: &[Move]) -> Quorridor {
+    let mut position = start.clone();
+    for mov in moves {
+        position.make_move(mov);
+    }
+    position
+}
+
+// Names accepted by create_engine
+pub const ENGINE_NAMES: [&str; 8] = ["mcts", "rollout", "network", "alphabeta", "random", "shortest", "walls", "oneply"];
+
+// Engine with default settings for one of ENGINE_NAMES
+pub fn create_engine(name: &str, threads: usize, table_mb: usize) -> Result<Box<dyn Engine>, String> {
+    EngineConfig { threads, table_mb, ..EngineConfig::parse(name)? }.build()
+}
+
+pub const DEFAULT_EXPLORATION: f64 = 0.5;
+
+// Positions later in a game search faster; a timed search's table has room
+// for this many times the playouts the last search's rate predicts
+const TABLE_RATE_MARGIN: f64 = 2.0;
+// Searches shorter than this say too little about the playout rate
+const MIN_RATE_SAMPLE: Duration = Duration::from_millis(20);
+
+// The MCTS backend. The tree of the last search (or ponder) is kept until the
+// next set_position, which carries the part under the moves played into the
+// next search.
+pub struct MctsEngine {
+    evaluator: MyEvaluator,
+    exploration: f64,
+    threads: usize,
+    table_mb: usize,
+    start: Quorridor,
+    history: Vec<Move>,
+    position: Quorridor,
+    reuse: TreeReuse,
+    last_tree: Option<(MCTSManager<MyMCTS>, usize)>,  // With the history length at its root
+    ponder: Option<(Ponder, usize)>,
+    playout_rate: Option<f64>,  // Playouts per second of the last search, to size the next table
+    stop: Arc<AtomicBool>,
+}
+
+impl MctsEngine {
+    pub fn new(evaluator: MyEvaluator, exploration: f64, threads: usize, table_mb: usize) -> Self {
+        MctsEngine {
+            evaluator,
+            exploration,
+            threads: threads.max(1),
+            table_mb,
+            start: Quorridor::default(),
+            history: Vec::new(),
+            position: Quorridor::default(),
+            reuse: TreeReuse::new(),
+            last_tree: None,
+            ponder: None,
+            playout_rate: None,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Engine for MctsEngine {
+    fn name(&self) -> String {
+        format!("mcts(c={}, threads={})", self.exploration, self.threads)
+    }
+
+    fn new_game(&mut self) {
+        self.stop_ponder();
+        self.last_tree = None;
+        self.reuse.clear();
+        self.start = Quorridor::default();
+        self.history.clear();
+        self.position = Quorridor::default();
+    }
+
+    fn set_position(&mut self, start: &Quorridor, moves: &[Move]) {
+        self.stop_ponder();
+        let root_len = self.last_tree.as_ref().map_or(self.history.len(), |(_, len)| *len);
+        let continues = start.zobrist == self.start.zobrist
+            && moves.get(..root_len) == self.history.get(..root_len);
+
+        match self.last_tree.take() {
+            Some((mcts, root_len)) if continues => self.reuse.advance(&mcts, &moves[root_len..]),
+            _ if !continues => self.reuse.clear(),
+            _ => {}
+        }
+
+        self.start = start.clone();
+        self.history = moves.to_vec();
+        self.position = position_after(start, moves);
+    }
+
+    fn search(&mut self, limits: &SearchLimits) -> SearchResult {
+        self.stop_ponder();
+        self.last_tree = None;
+
+        // A playout creates at most one node, so the playouts a search can make
+        // bound the table needed: the budget, or for a timed search the time
+        // allowed at the last search's rate with room to spare. Only the first
+        // timed search of an engine allocates the full table.
+        let playouts = match (limits.playouts, limits.budget(&self.position), self.playout_rate) {
+            (Some(playouts), _, _) => Some(playouts),
+            (None, None, _) => Some(DEFAULT_PLAYOUTS),
+            (None, Some(budget), Some(rate)) => Some((rate * budget.hard.as_secs_f64() * TABLE_RATE_MARGIN) as u64),
+            (None, Some(_), None) => None,
+        };
+        let table = match playouts {
+            Some(playouts) => QuorridorTable::for_nodes(playouts, self.table_mb, ReplacementPolicy::KeepOldest),
+            None => QuorridorTable::with_memory_mb(self.table_mb, ReplacementPolicy::KeepOldest),
+        };
+        let table_stats = table.stats();
+        // Bounds memory when nothing else ends the search, as with go infinite. A
+        // smaller table than that only loses transpositions, never nodes. The
+        // root takes no slot.
+        let node_limit = QuorridorTable::node_capacity_for_mb(self.table_mb) + 1;
+        let mut mcts = MCTSManager::new(
+            self.position.clone(),
+            MyMCTS::with_node_limit(node_limit),
+            self.evaluator.with_inherited(self.reuse.inherited()),
+            PriorUCTPolicy::new(self.exploration),
+            table
+        );
+        let stats = run_search_until(&mut mcts, limits, self.threads, &self.stop);
+        if stats.elapsed >= MIN_RATE_SAMPLE {
+            self.playout_rate = Some(stats.playouts as f64 / stats.elapsed.as_secs_f64());
+        }
+
+        let mut result = SearchResult::from_mcts(&mcts, &stats);
+        result.transposition = Some(table_stats.snapshot());
+        self.last_tree = Some((mcts, self.history.len()));
+        result
+    }
+
+    fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    fn start_ponder(&mut self) {
+        self.stop_ponder();
+        if self.position.game_over() {
+            return;
+        }
+        // Seed the ponder search with what the last search learned about this position
+        if let Some((mcts, root_len)) = self.last_tree.take() {
+            self.reuse.advance(&mcts, &self.history[root_len..]);
+        }
+        let ponder = Ponder::start(&self.position, &self.evaluator, &self.reuse, self.exploration, self.threads, self.table_mb);
+        self.ponder = Some((ponder, self.history.len()));
+    }
+
+    fn stop_ponder(&mut self) -> Option<PonderStats> {
+        let (ponder, root_len) = self.ponder.take()?;
+        let (mcts, stats) = ponder.stop();
+        self.last_tree = Some((mcts, root_len));
+        Some(stats)
+    }
+}
+
+// The negamax backend behind the Engine interface
+pub struct AlphaBetaEngine {
+    search: AlphaBeta,
+    position: Quorridor,
+}
+
+impl AlphaBetaEngine {
+    pub fn new(search: AlphaBeta) -> Self {
+        AlphaBetaEngine { search, position: Quorridor::default() }
+    }
+}
+
+impl Engine for AlphaBetaEngine {
+    fn name(&self) -> String {
+        "alphabeta".to_string()
+    }
+
+    fn new_game(&mut self) {
+        self.search.new_game();
+        self.position = Quorridor::default();
+    }
+
+    fn set_position(&mut self, start: &Quorridor, moves: &[Move]) {
+        self.position = position_after(start, moves);
+    }
+
+    fn search(&mut self, limits: &SearchLimits) -> SearchResult {
+        self.search.search(&self.position, limits)
+    }
+
+    fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.search.stop_flag()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endgame::{Proven, test_race};
+    use crate::search::StopReason;
+
+    #[test]
+    fn test_every_engine_plays_a_legal_move() {
+        for name in ["mcts", "alphabeta", "random", "shortest", "walls", "oneply"] {
+            let mut engine = create_engine(name, 1, 1).unwrap();
+            let start = Quorridor::default();
+            engine.set_position(&start, &[Move::Down]);
+            let limits = SearchLimits { playouts: Some(50), depth: Some(1), ..Default::default() };
+            let best = engine.search(&limits).best_move.expect("a move");
+            assert!(position_after(&start, &[Move::Down]).legal_moves().contains(&best), "{} played {:?}", name, best);
+
+            // Player 1 could shut player 0 in, which the heuristic would score as a win
+            let cornered = crate::quorridor::cornered_position();
+            engine.set_position(&cornered, &[]);
+            let limits = SearchLimits { playouts: Some(2000), depth: Some(2), ..Default::default() };
+            let best = engine.search(&limits).best_move.expect("a move");
+            assert!(cornered.legal_moves().contains(&best), "{} played {:?} when cornered", name, best);
+        }
+    }
+
+    #[test]
+    fn test_mcts_engine_reuses_tree_along_the_game() {
+        let mut engine = MctsEngine::new(MyEvaluator::new(), DEFAULT_EXPLORATION, 1, 1);
+        let start = Quorridor::default();
+        engine.set_position(&start, &[]);
+        let first = engine.search(&SearchLimits::playouts(2000)).best_move.unwrap();
+
+        engine.set_position(&start, &[first]);
+        let result = engine.search(&SearchLimits::playouts(10));
+        assert!(result.inherited_visits > 0);
+
+        // An unrelated position drops the inherited statistics
+        engine.set_position(&start, &[Move::Left]);
+        let result = engine.search(&SearchLimits::playouts(10));
+        assert_eq!(result.inherited_visits, 0);
+    }
+
+    #[test]
+    fn test_infinite_mcts_search_stops_at_the_node_limit() {
+        // As go infinite asks for; a 1 MB table holds slots for some thousands of nodes
+        let mut engine = MctsEngine::new(MyEvaluator::new(), DEFAULT_EXPLORATION, 1, 1);
+        engine.set_position(&Quorridor::default(), &[]);
+        let result = engine.search(&SearchLimits::playouts(u64::MAX));
+        assert_eq!(result.stop_reason, StopReason::NodeLimit);
+        assert!(result.tree_size <= QuorridorTable::node_capacity_for_mb(1) + 1);
+    }
+
+    #[test]
+    fn test_mcts_engine_stops_on_a_proven_race() {
+        // No walls left: player 0 is three steps from goal, player 1 four
+        let start = test_race((1, 11), (17, 9), 0);
+        let mut engine = MctsEngine::new(MyEvaluator::new(), DEFAULT_EXPLORATION, 1, 1);
+        engine.set_position(&start, &[]);
+        let result = engine.search(&SearchLimits::playouts(5000));
+        assert_eq!(result.proven, Some(Proven::Win(5)));
+        assert_eq!(result.stop_reason, StopReason::Proven);
+        assert_eq!(result.best_move, Some(Move::Down));
+        let down = result.root_moves.iter().find(|m| m.mov == Move::Down).unwrap();
+        assert_eq!((down.proven, down.win_probability), (Some(Proven::Win(5)), Some(1.0)));
+    }
+}
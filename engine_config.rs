@@ -0,0 +1,158 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::alphabeta::AlphaBeta;
+use crate::bots::{BaselineBot, BaselineKind};
+use crate::engine::{Engine, MctsEngine, AlphaBetaEngine, DEFAULT_EXPLORATION, ENGINE_NAMES};
+use crate::mcts_impl::MyEvaluator;
+use crate::move_filter::WallFilter;
+use crate::opening_book::{BookEngine, OpeningBook, DEFAULT_BOOK_DEPTH};
+use crate::policy_network::PolicyNetwork;
+use crate::rollout::RolloutConfig;
+use crate::time_control::SearchLimits;
+use crate::transposition::DEFAULT_TABLE_MB;
+
+pub const DEFAULT_NETWORK_PATH: &str = "model.safetensors";
+
+// An engine and its search settings, written as `kind[:key=value,...]`, e.g.
+//   mcts:playouts=2000,c=0.7
+//   network:net=checkpoints/gen5.safetensors,playouts=800
+//   alphabeta:depth=4
+//   random
+//   alphabeta:depth=4,book=book.json,bookdepth=6
+// Keys: playouts, movetime (ms), depth, c, net, threads, hash (MB), prune (true/false),
+// book (opening book file), bookdepth (plies played from the book),
+// solver (true/false, MCTS looks up wall-less races exactly; default true)
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineConfig {
+    pub spec: String,
+    pub kind: String,
+    pub playouts: Option<u64>,
+    pub move_time: Option<Duration>,
+    pub depth: Option<u32>,
+    pub exploration: f64,
+    pub network: Option<String>,
+    pub threads: usize,
+    pub table_mb: usize,
+    pub prune_walls: bool,
+    pub endgame_solver: bool,
+    pub book: Option<String>,
+    pub book_depth: usize,
+}
+
+impl EngineConfig {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (kind, options) = spec.split_once(':').unwrap_or((spec, ""));
+        if !ENGINE_NAMES.contains(&kind) {
+            return Err(format!("unknown engine '{}', expected one of {}", kind, ENGINE_NAMES.join(", ")));
+        }
+
+        let mut config = EngineConfig {
+            spec: spec.to_string(),
+            kind: kind.to_string(),
+            playouts: None,
+            move_time: None,
+            depth: None,
+            exploration: DEFAULT_EXPLORATION,
+            network: None,
+            threads: 1,
+            table_mb: DEFAULT_TABLE_MB,
+            prune_walls: false,
+            endgame_solver: true,
+            book: None,
+            book_depth: DEFAULT_BOOK_DEPTH,
+        };
+
+        for option in options.split(',').filter(|o| !o.is_empty()) {
+            let (key, value) = option.split_once('=')
+                .ok_or_else(|| format!("expected key=value in '{}'", option))?;
+            let invalid = || format!("invalid value '{}' for {}", value, key);
+            match key {
+                "playouts" => config.playouts = Some(value.parse().map_err(|_| invalid())?),
+                "movetime" => config.move_time = Some(Duration::from_millis(value.parse().map_err(|_| invalid())?)),
+                "depth" => config.depth = Some(value.parse().map_err(|_| invalid())?),
+                "c" => config.exploration = check_exploration(value.parse().map_err(|_| invalid())?)?,
+                "net" => config.network = Some(value.to_string()),
+                "threads" => config.threads = value.parse().map_err(|_| invalid())?,
+                "hash" => config.table_mb = value.parse().map_err(|_| invalid())?,
+                "prune" => config.prune_walls = value.parse().map_err(|_| invalid())?,
+                "solver" => config.endgame_solver = value.parse().map_err(|_| invalid())?,
+                "book" => config.book = Some(value.to_string()),
+                "bookdepth" => config.book_depth = value.parse().map_err(|_| invalid())?,
+                _ => return Err(format!("unknown option '{}' in '{}'", key, spec)),
+            }
+        }
+        if config.network.is_some() && config.kind != "network" {
+            return Err(format!("'net' only applies to the network engine, not {}", config.kind));
+        }
+        Ok(config)
+    }
+
+    pub fn limits(&self) -> SearchLimits {
+        SearchLimits { playouts: self.playouts, move_time: self.move_time, clock: None, depth: self.depth }
+    }
+
+    pub fn build(&self) -> Result<Box<dyn Engine>, String> {
+        let engine = self.build_search()?;
+        match &self.book {
+            Some(path) => Ok(Box::new(BookEngine::new(engine, Arc::new(OpeningBook::load(path)?), self.book_depth))),
+            None => Ok(engine),
+        }
+    }
+
+    fn build_search(&self) -> Result<Box<dyn Engine>, String> {
+        let evaluator = match self.kind.as_str() {
+            "mcts" => MyEvaluator::new(),
+            "rollout" => MyEvaluator::with_rollouts(RolloutConfig::default()),
+            "network" => {
+                let path = self.network.as_deref().unwrap_or(DEFAULT_NETWORK_PATH);
+                let mut network = PolicyNetwork::new();
+                network.load(path).map_err(|e| format!("failed to load {}: {}", path, e))?;
+                MyEvaluator::with_network(Arc::new(Mutex::new(network)))
+            }
+            "alphabeta" => return Ok(Box::new(AlphaBetaEngine::new(AlphaBeta::with_table_mb(self.table_mb)))),
+            kind => {
+                let kind = BaselineKind::from_name(kind).ok_or_else(|| format!("unknown engine '{}'", kind))?;
+                return Ok(Box::new(BaselineBot::new(kind)));
+            }
+        };
+        let evaluator = evaluator.with_wall_filter(self.prune_walls.then(WallFilter::default))
+            .with_endgame_solver(self.endgame_solver);
+        // All threads would queue on the one network behind its mutex
+        let threads = if self.kind == "network" { 1 } else { self.threads };
+        Ok(Box::new(MctsEngine::new(evaluator, self.exploration, threads, self.table_mb)))
+    }
+}
+
+// The UCT exploration constant; the search asserts it is a positive number
+pub fn check_exploration(c: f64) -> Result<f64, String> {
+    if !c.is_finite() || c <= 0.0 {
+        return Err(format!("exploration constant c must be positive, got {}", c));
+    }
+    Ok(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_options() {
+        let config = EngineConfig::parse("network:net=ckpt/gen3.safetensors,playouts=800,c=1.2,prune=true").unwrap();
+        assert_eq!(config.kind, "network");
+        assert_eq!(config.network.as_deref(), Some("ckpt/gen3.safetensors"));
+        assert_eq!(config.playouts, Some(800));
+        assert_eq!(config.exploration, 1.2);
+        assert!(config.prune_walls);
+        assert!(config.endgame_solver);
+        assert!(!EngineConfig::parse("mcts:solver=false").unwrap().endgame_solver);
+
+        assert_eq!(EngineConfig::parse("random").unwrap().limits().playouts, None);
+        assert!(EngineConfig::parse("mcts:playouts=lots").is_err());
+        assert!(EngineConfig::parse("mcts:net=x").is_err());
+        for c in ["0", "-1", "nan", "inf"] {
+            assert!(EngineConfig::parse(&format!("mcts:c={}", c)).is_err());
+        }
+        assert!(EngineConfig::parse("stockfish").is_err());
+    }
+}
@@ -0,0 +1,25 @@
+use std::env;
+use std::io;
+
+use quorridor::protocol::{Session, serve};
+
+fn print_usage() {
+    println!("Usage: quorridor-engine [--engine SPEC]");
+    println!("  Speaks a line-based protocol modeled on UCI on stdin/stdout; send 'uqi' to start.");
+    println!("  SPEC is an engine spec such as mcts:playouts=2000 or alphabeta:depth=4.");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|a| a == "--help") {
+        print_usage();
+        return;
+    }
+    let mut session = Session::new(io::stdout());
+    if let Some(spec) = args.iter().position(|a| a == "--engine").and_then(|i| args.get(i + 1)) {
+        session.handle_line(&format!("setoption name Engine value {}", spec));
+    }
+
+    // Searches run on this thread; stdin is read on another
+    serve(session, io::BufReader::new(io::stdin()));
+}
@@ -0,0 +1,239 @@
+use mcts::GameState;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::time::Instant;
+
+use crate::engine::Engine;
+use crate::moves::Move;
+use crate::notation::{move_to_notation, parse_move};
+use crate::quorridor::Quorridor;
+use crate::time_control::{GameClock, SearchLimits};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Termination {
+    Goal,       // A pawn reached its goal row
+    MaxPlies,   // Adjudicated as a draw at the ply limit
+    NoMove,     // The side to move had no move to play; they lose
+    Time,       // The side to move ran out of time on the game clock; they lose
+    IllegalMove,  // The side to move's engine answered with an illegal move; they lose
+}
+
+// A finished game in move notation, one JSON object per line in record files
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub players: [String; 2],
+    pub moves: Vec<String>,
+    pub winner: Option<usize>,
+    pub termination: Termination,
+}
+
+impl GameRecord {
+    // The moves of the record, checked against the rules from the start position
+    pub fn parse_moves(&self) -> Result<Vec<Move>, String> {
+        let mut state = Quorridor::default();
+        let mut moves = Vec::with_capacity(self.moves.len());
+        for (ply, text) in self.moves.iter().enumerate() {
+            let mov = parse_move(&state, text)
+                .filter(|mov| state.legal_moves().contains(mov))
+                .ok_or_else(|| format!("illegal move '{}' at ply {}", text, ply + 1))?;
+            state.make_move(&mov);
+            moves.push(mov);
+        }
+        Ok(moves)
+    }
+
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("GameRecord is always serializable")
+    }
+}
+
+pub fn append_record(path: &str, record: &GameRecord) -> std::io::Result<()> {
+    let mut file = File::options().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record.to_json_line())
+}
+
+pub fn load_records(path: &str) -> Result<Vec<GameRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    BufReader::new(file).lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|(i, line)| {
+            let line = line.map_err(|e| format!("{}: {}", path, e))?;
+            serde_json::from_str(&line).map_err(|e| format!("{} line {}: {}", path, i + 1, e))
+        })
+        .collect()
+}
+
+// Random legal moves shared by both games of a pair, so each opening is played from both sides
+pub fn random_opening(seed: u64, plies: usize) -> Vec<Move> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut state = Quorridor::default();
+    let mut moves = Vec::new();
+    while moves.len() < plies {
+        let candidates: Vec<Move> = state.legal_moves().into_iter()
+            .filter(|mov| {
+                let mut child = state.clone();
+                child.make_move(mov);
+                !child.game_over()
+            })
+            .collect();
+        let Some(mov) = candidates.choose(&mut rng) else { break };
+        state.make_move(mov);
+        moves.push(mov.clone());
+    }
+    moves
+}
+
+// Play one game between two engines from the start position. `opening` is
+// played first without asking the engines; the game is a draw after max_plies.
+// With a clock each search is charged to its side, which loses on running out.
+// An engine that answers with an illegal move loses; the move is not recorded.
+pub fn play_game(mut engines: [&mut dyn Engine; 2], limits: [&SearchLimits; 2], opening: &[Move], max_plies: usize, mut clock: Option<GameClock>) -> GameRecord {
+    let players = [engines[0].name(), engines[1].name()];
+    let start = Quorridor::default();
+    for engine in engines.iter_mut() {
+        engine.new_game();
+    }
+
+    let mut state = start.clone();
+    let mut played: Vec<Move> = Vec::new();
+    let mut notation = Vec::new();
+    let mut no_move = false;
+    let mut flagged = false;
+    let mut illegal = false;
+
+    while !state.game_over() && played.len() < max_plies {
+        let player = state.active_player;
+        let mov = match opening.get(played.len()) {
+            Some(mov) => Some(mov.clone()),
+            None => {
+                let limits = SearchLimits { clock: clock.as_ref().map(|c| c.state(player)), ..limits[player].clone() };
+                engines[player].set_position(&start, &played);
+                let move_start = Instant::now();
+                let best_move = engines[player].search(&limits).best_move;
+                if let Some(clock) = clock.as_mut() {
+                    clock.record_move(player, move_start.elapsed());
+                    if clock.flagged(player) {
+                        flagged = true;
+                        break;
+                    }
+                }
+                best_move
+            }
+        };
+        let Some(mov) = mov else {
+            no_move = true;
+            break;
+        };
+        if !state.legal_moves().contains(&mov) {
+            illegal = true;
+            break;
+        }
+        notation.push(move_to_notation(&state, &mov));
+        state.make_move(&mov);
+        played.push(mov);
+    }
+
+    let (winner, termination) = if state.game_over() {
+        (state.winner(), Termination::Goal)
+    } else if flagged {
+        (Some(1 - state.active_player), Termination::Time)
+    } else if illegal {
+        (Some(1 - state.active_player), Termination::IllegalMove)
+    } else if no_move {
+        (Some(1 - state.active_player), Termination::NoMove)
+    } else {
+        (None, Termination::MaxPlies)
+    };
+    GameRecord { players, moves: notation, winner, termination }
+}
+
+// An unfinished game between players "a" and "b", for tests of the modules reading records
+#[cfg(test)]
+pub(crate) fn test_record(moves: &[&str]) -> GameRecord {
+    GameRecord {
+        players: ["a".to_string(), "b".to_string()],
+        moves: moves.iter().map(|m| m.to_string()).collect(),
+        winner: None,
+        termination: Termination::MaxPlies,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::{BaselineBot, BaselineKind};
+    use crate::search_result::SearchResult;
+    use crate::wall::Orientation;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    // Answers every search with the same move, legal or not
+    struct FixedMove(Move, Arc<AtomicBool>);
+
+    impl Engine for FixedMove {
+        fn name(&self) -> String {
+            "fixed".to_string()
+        }
+
+        fn new_game(&mut self) {}
+
+        fn set_position(&mut self, _: &Quorridor, _: &[Move]) {}
+
+        fn search(&mut self, _: &SearchLimits) -> SearchResult {
+            SearchResult::without_search(&Quorridor::default(), Some(self.0.clone()), Duration::ZERO)
+        }
+
+        fn stop_flag(&self) -> Arc<AtomicBool> {
+            self.1.clone()
+        }
+    }
+
+    #[test]
+    fn test_record_round_trips_through_json() {
+        let mut a = BaselineBot::new(BaselineKind::ShortestPath);
+        let mut b = BaselineBot::new(BaselineKind::GreedyWalls);
+        let limits = SearchLimits::default();
+        let record = play_game([&mut a, &mut b], [&limits, &limits], &[], 300, None);
+        assert_eq!(record.termination, Termination::Goal);
+
+        let parsed: GameRecord = serde_json::from_str(&record.to_json_line()).unwrap();
+        let moves = parsed.parse_moves().unwrap();
+        let mut state = Quorridor::default();
+        for mov in &moves {
+            state.make_move(mov);
+        }
+        assert_eq!(state.winner(), record.winner);
+    }
+
+    #[test]
+    fn test_empty_clock_loses_on_time() {
+        let mut a = BaselineBot::new(BaselineKind::ShortestPath);
+        let mut b = BaselineBot::new(BaselineKind::ShortestPath);
+        let limits = SearchLimits::default();
+        let clock = GameClock::new(Duration::ZERO, Duration::ZERO);
+        // The opening move is not charged; player 1's first search is
+        let record = play_game([&mut a, &mut b], [&limits, &limits], &[Move::Down], 300, Some(clock));
+        assert_eq!(record.termination, Termination::Time);
+        assert_eq!(record.winner, Some(0));
+        assert_eq!(record.moves, vec!["e2"]);
+    }
+
+    #[test]
+    fn test_illegal_move_loses() {
+        let mut a = BaselineBot::new(BaselineKind::ShortestPath);
+        // The wall goes down once; the second time it overlaps itself
+        let mut b = FixedMove(Move::PlaceWall(8, 8, Orientation::Horizontal), Arc::default());
+        let limits = SearchLimits::default();
+        let record = play_game([&mut a, &mut b], [&limits, &limits], &[], 300, None);
+        assert_eq!(record.termination, Termination::IllegalMove);
+        assert_eq!(record.winner, Some(0));
+        assert_eq!(record.moves.len(), 3);
+        assert!(record.parse_moves().is_ok());
+    }
+}
@@ -0,0 +1,637 @@
+// Game server for playing and watching in a browser: an HTTP API to create
+// games, take seats as a human or attach an engine, and submit moves in
+// notation, plus a WebSocket per game streaming its state after every change.
+//   GET  /                      the browser client
+//   GET  /games                 every game
+//   POST /games                 new game with both seats open
+//   GET  /games/{id}            one game
+//   POST /games/{id}/join       {"seat": 0|1} (optional) -> {"seat", "token"}
+//   POST /games/{id}/engine     {"seat": 0|1, "spec": "mcts:playouts=2000"}
+//   POST /games/{id}/moves      {"token": "...", "move": "e2"}
+//   GET  /games/{id}/ws         WebSocket of game states
+// A game is removed once it finishes and its final state has been sent out.
+use mcts::GameState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tungstenite::{Message, WebSocket, handshake::derive_accept_key, protocol::Role};
+
+use crate::engine_config::EngineConfig;
+use crate::game_record::{GameRecord, Termination, append_record};
+use crate::moves::Move;
+use crate::notation::{move_to_notation, parse_move, square_name};
+use crate::quorridor::Quorridor;
+
+// Games still running at this many plies are drawn, as in arena
+pub const MAX_PLIES: usize = 200;
+
+const INDEX_HTML: &str = include_str!("server.html");
+
+// Engines a client may seat. Clients cannot name files (net, book), the
+// tables and threads they ask for are capped, and their search limits are
+// lowered to the server's.
+const CLIENT_ENGINES: [&str; 8] = ["mcts", "rollout", "network", "alphabeta", "random", "shortest", "walls", "oneply"];
+const MAX_CLIENT_HASH_MB: usize = 256;
+const MAX_CLIENT_THREADS: usize = 4;
+const MAX_CLIENT_PLAYOUTS: u64 = 200_000;
+const MAX_CLIENT_MOVE_TIME: Duration = Duration::from_secs(10);
+const MAX_CLIENT_DEPTH: u32 = 6;
+
+// An API failure: HTTP status and message
+#[derive(Debug, PartialEq)]
+pub struct ApiError(pub u16, pub String);
+
+fn not_found(what: &str) -> ApiError {
+    ApiError(404, format!("no such {}", what))
+}
+
+enum Seat {
+    Open,
+    Human { token: String },
+    // The worker thread owning the engine; it searches each position sent here.
+    // `worker` tells its moves from those of an engine it replaced.
+    Engine { spec: String, worker: u64, requests: Sender<Vec<Move>>, stop: Arc<AtomicBool> },
+}
+
+// An engine losing its seat, by being replaced or its game being removed,
+// abandons any search it is running; nobody is waiting for the move
+impl Drop for Seat {
+    fn drop(&mut self) {
+        if let Seat::Engine { stop, .. } = self {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+struct Game {
+    state: Quorridor,
+    moves: Vec<Move>,
+    notation: Vec<String>,
+    seats: [Seat; 2],
+    outcome: Option<(Option<usize>, Termination)>,
+    recorded: bool,
+    subscribers: Vec<Sender<String>>,
+}
+
+// Everything a client needs to draw a game
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameView {
+    pub id: u64,
+    pub seats: [String; 2],  // "open", "human" or the engine spec
+    pub moves: Vec<String>,
+    pub to_move: usize,
+    pub pawns: [String; 2],
+    pub walls_remaining: [usize; 2],
+    pub walls: Vec<String>,  // Placed walls in notation
+    pub legal_moves: Vec<String>,  // Empty once the game is over
+    pub finished: bool,
+    pub winner: Option<usize>,
+    pub termination: Option<Termination>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Joined {
+    pub seat: usize,
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+struct JoinRequest {
+    seat: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct EngineRequest {
+    seat: usize,
+    spec: String,
+}
+
+#[derive(Deserialize)]
+struct MoveRequest {
+    token: String,
+    #[serde(rename = "move")]
+    mov: String,
+}
+
+impl Game {
+    fn view(&self, id: u64) -> GameView {
+        let seats = [0, 1].map(|seat| match &self.seats[seat] {
+            Seat::Open => "open".to_string(),
+            Seat::Human { .. } => "human".to_string(),
+            Seat::Engine { spec, .. } => spec.clone(),
+        });
+        let legal_moves = match self.outcome {
+            Some(_) => Vec::new(),
+            None => self.state.legal_moves().iter().map(|mov| move_to_notation(&self.state, mov)).collect(),
+        };
+        GameView {
+            id,
+            seats,
+            moves: self.notation.clone(),
+            to_move: self.state.active_player,
+            pawns: self.state.player_pieces.map(|piece| square_name(piece.x, piece.y)),
+            walls_remaining: self.state.walls_remaining,
+            walls: self.notation.iter().filter(|text| text.ends_with(['h', 'v'])).cloned().collect(),
+            legal_moves,
+            finished: self.outcome.is_some(),
+            winner: self.outcome.and_then(|(winner, _)| winner),
+            termination: self.outcome.map(|(_, termination)| termination),
+        }
+    }
+
+    fn play(&mut self, mov: Move) {
+        self.notation.push(move_to_notation(&self.state, &mov));
+        self.state.make_move(&mov);
+        self.moves.push(mov);
+        if self.state.game_over() {
+            self.outcome = Some((self.state.winner(), Termination::Goal));
+        } else if self.state.legal_moves().is_empty() {
+            self.outcome = Some((Some(1 - self.state.active_player), Termination::NoMove));
+        } else if self.moves.len() >= MAX_PLIES {
+            self.outcome = Some((None, Termination::MaxPlies));
+        }
+    }
+}
+
+// All games of a server, independent of HTTP so it can be driven directly
+#[derive(Default)]
+pub struct Lobby {
+    games: Mutex<HashMap<u64, Game>>,
+    next_id: AtomicU64,
+    next_worker: AtomicU64,
+    records: Option<String>,  // Finished games are appended here
+}
+
+impl Lobby {
+    pub fn new(records: Option<String>) -> Arc<Self> {
+        Arc::new(Lobby { records, ..Default::default() })
+    }
+
+    pub fn create(&self) -> GameView {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let game = Game {
+            state: Quorridor::default(),
+            moves: Vec::new(),
+            notation: Vec::new(),
+            seats: [Seat::Open, Seat::Open],
+            outcome: None,
+            recorded: false,
+            subscribers: Vec::new(),
+        };
+        let view = game.view(id);
+        self.games.lock().unwrap().insert(id, game);
+        view
+    }
+
+    pub fn view(&self, id: u64) -> Result<GameView, ApiError> {
+        self.games.lock().unwrap().get(&id).map(|game| game.view(id)).ok_or_else(|| not_found("game"))
+    }
+
+    pub fn list(&self) -> Vec<GameView> {
+        let games = self.games.lock().unwrap();
+        let mut views: Vec<GameView> = games.iter().map(|(&id, game)| game.view(id)).collect();
+        views.sort_by_key(|view| view.id);
+        views
+    }
+
+    // Take `seat`, or the first open one, as a human player
+    pub fn join(&self, id: u64, seat: Option<usize>) -> Result<Joined, ApiError> {
+        let mut games = self.games.lock().unwrap();
+        let game = games.get_mut(&id).ok_or_else(|| not_found("game"))?;
+        let seat = match seat {
+            Some(seat) if seat > 1 => return Err(ApiError(400, "seat must be 0 or 1".to_string())),
+            Some(seat) => seat,
+            None => (0..2).find(|&seat| matches!(game.seats[seat], Seat::Open))
+                .ok_or_else(|| ApiError(409, "both seats are taken".to_string()))?,
+        };
+        if !matches!(game.seats[seat], Seat::Open) {
+            return Err(ApiError(409, format!("seat {} is taken", seat)));
+        }
+        let token = format!("{:016x}", rand::random::<u64>());
+        game.seats[seat] = Seat::Human { token: token.clone() };
+        self.changed(id, game);
+        Ok(Joined { seat, token })
+    }
+
+    // Seat an engine built from `spec`; it may replace an engine but not a human
+    pub fn attach_engine(self: &Arc<Self>, id: u64, seat: usize, spec: &str) -> Result<GameView, ApiError> {
+        let config = client_engine(spec).map_err(|e| ApiError(400, e))?;
+        if seat > 1 {
+            return Err(ApiError(400, "seat must be 0 or 1".to_string()));
+        }
+        engine_seat(&self.games.lock().unwrap(), id, seat)?;
+
+        // Built on the worker thread since engines are not Send. The answer
+        // waits for the build, without holding the lobby, so that a failure
+        // (a missing network file, say) is reported rather than leaving the seat waiting.
+        let worker = self.next_worker.fetch_add(1, Ordering::Relaxed);
+        let (requests, receiver) = mpsc::channel();
+        let (built_sender, built) = mpsc::channel();
+        let lobby = Arc::clone(self);
+        thread::spawn(move || engine_worker(lobby, id, seat, worker, config, built_sender, receiver));
+        let stop = built.recv().map_err(|_| ApiError(500, "the engine worker stopped".to_string()))?
+            .map_err(|e| ApiError(400, e))?;
+
+        // The game may have finished or the seat gone to a human meanwhile;
+        // dropping `requests` then ends the worker
+        let mut games = self.games.lock().unwrap();
+        engine_seat(&games, id, seat)?;
+        let game = games.get_mut(&id).unwrap();
+        game.seats[seat] = Seat::Engine { spec: spec.to_string(), worker, requests, stop };
+        self.changed(id, game);
+        Ok(game.view(id))
+    }
+
+    // Play a human's move in notation
+    pub fn submit_move(&self, id: u64, token: &str, text: &str) -> Result<GameView, ApiError> {
+        let mut games = self.games.lock().unwrap();
+        let game = games.get_mut(&id).ok_or_else(|| not_found("game"))?;
+        let seat = (0..2).find(|&seat| matches!(&game.seats[seat], Seat::Human { token: t } if t == token))
+            .ok_or_else(|| ApiError(403, "unknown token".to_string()))?;
+        if game.outcome.is_some() {
+            return Err(ApiError(409, "the game is over".to_string()));
+        }
+        if game.state.active_player != seat {
+            return Err(ApiError(409, "not your turn".to_string()));
+        }
+        let mov = parse_move(&game.state, text)
+            .filter(|mov| game.state.legal_moves().contains(mov))
+            .ok_or_else(|| ApiError(400, format!("illegal move '{}'", text)))?;
+        game.play(mov);
+        self.changed(id, game);
+        let view = game.view(id);
+        remove_if_finished(&mut games, id);
+        Ok(view)
+    }
+
+    // An engine's move for the position after `ply` plies. Dropped if the game
+    // moved on or the engine was replaced; an illegal move or none at all loses
+    // the game for the engine's seat, as in arena.
+    fn engine_move(&self, id: u64, seat: usize, worker: u64, ply: usize, mov: Option<Move>) {
+        let mut games = self.games.lock().unwrap();
+        let Some(game) = games.get_mut(&id) else { return };
+        let current = matches!(game.seats[seat], Seat::Engine { worker: w, .. } if w == worker);
+        if !current || game.moves.len() != ply || game.state.active_player != seat || game.outcome.is_some() {
+            return;
+        }
+        match mov {
+            Some(mov) if game.state.legal_moves().contains(&mov) => game.play(mov),
+            Some(mov) => {
+                eprintln!("Game {} seat {}: illegal move {:?}", id, seat, mov);
+                game.outcome = Some((Some(1 - seat), Termination::IllegalMove));
+            }
+            None => game.outcome = Some((Some(1 - seat), Termination::NoMove)),
+        }
+        self.changed(id, game);
+        remove_if_finished(&mut games, id);
+    }
+
+    // State updates for game `id`, starting with its current state
+    pub fn subscribe(&self, id: u64) -> Result<Receiver<String>, ApiError> {
+        let mut games = self.games.lock().unwrap();
+        let game = games.get_mut(&id).ok_or_else(|| not_found("game"))?;
+        let (sender, receiver) = mpsc::channel();
+        sender.send(serde_json::to_string(&game.view(id)).unwrap()).unwrap();
+        game.subscribers.push(sender);
+        Ok(receiver)
+    }
+
+    // Tell subscribers, record a finished game, and hand the position to an
+    // engine whose turn it is
+    fn changed(&self, id: u64, game: &mut Game) {
+        let json = serde_json::to_string(&game.view(id)).unwrap();
+        game.subscribers.retain(|subscriber| subscriber.send(json.clone()).is_ok());
+
+        if let Some((winner, termination)) = game.outcome {
+            if let Some(path) = &self.records && !game.recorded {
+                game.recorded = true;
+                let players = [0, 1].map(|seat| match &game.seats[seat] {
+                    Seat::Engine { spec, .. } => spec.clone(),
+                    _ => "human".to_string(),
+                });
+                let record = GameRecord { players, moves: game.notation.clone(), winner, termination };
+                if let Err(e) = append_record(path, &record) {
+                    eprintln!("Failed to write record: {}", e);
+                }
+            }
+            return;
+        }
+        if let Seat::Engine { requests, .. } = &game.seats[game.state.active_player] {
+            let _ = requests.send(game.moves.clone());
+        }
+    }
+}
+
+// Whether an engine may take `seat` of game `id`
+fn engine_seat(games: &HashMap<u64, Game>, id: u64, seat: usize) -> Result<(), ApiError> {
+    let game = games.get(&id).ok_or_else(|| not_found("game"))?;
+    if matches!(game.seats[seat], Seat::Human { .. }) {
+        return Err(ApiError(409, format!("seat {} is taken by a human", seat)));
+    }
+    Ok(())
+}
+
+// Once its final state has gone out a finished game is dropped, and with it
+// the senders that keep its engine workers and WebSockets running
+fn remove_if_finished(games: &mut HashMap<u64, Game>, id: u64) {
+    if games.get(&id).is_some_and(|game| game.outcome.is_some()) {
+        games.remove(&id);
+    }
+}
+
+// The engine settings of a client's spec, within what the server allows
+fn client_engine(spec: &str) -> Result<EngineConfig, String> {
+    let mut config = EngineConfig::parse(spec)?;
+    if !CLIENT_ENGINES.contains(&config.kind.as_str()) {
+        return Err(format!("engine '{}' is not available, expected one of {}", config.kind, CLIENT_ENGINES.join(", ")));
+    }
+    if config.network.is_some() || config.book.is_some() {
+        return Err("'net' and 'book' are not accepted from clients".to_string());
+    }
+    if config.table_mb > MAX_CLIENT_HASH_MB {
+        return Err(format!("hash is limited to {} MB", MAX_CLIENT_HASH_MB));
+    }
+    if !(1..=MAX_CLIENT_THREADS).contains(&config.threads) {
+        return Err(format!("threads must be from 1 to {}", MAX_CLIENT_THREADS));
+    }
+    // Unset limits already fall back to small defaults
+    config.playouts = config.playouts.map(|n| n.min(MAX_CLIENT_PLAYOUTS));
+    config.move_time = config.move_time.map(|t| t.min(MAX_CLIENT_MOVE_TIME));
+    config.depth = config.depth.map(|d| d.min(MAX_CLIENT_DEPTH));
+    Ok(config)
+}
+
+// Build the engine, report its stop flag or the error on `built`, then search each position requested
+fn engine_worker(lobby: Arc<Lobby>, id: u64, seat: usize, worker: u64, config: EngineConfig,
+    built: Sender<Result<Arc<AtomicBool>, String>>, requests: Receiver<Vec<Move>>) {
+    let mut engine = match config.build() {
+        Ok(engine) => engine,
+        Err(e) => {
+            let _ = built.send(Err(e));
+            return;
+        }
+    };
+    let _ = built.send(Ok(engine.stop_flag()));
+    let limits = config.limits();
+    let start = Quorridor::default();
+    // Ends once the seat is given to another engine or the game is removed, dropping the sender
+    while let Ok(moves) = requests.recv() {
+        engine.set_position(&start, &moves);
+        let mov = engine.search(&limits).best_move;
+        lobby.engine_move(id, seat, worker, moves.len(), mov);
+    }
+}
+
+// A running server; dropping it leaves the server running until the process exits
+pub struct RunningServer {
+    pub addr: SocketAddr,
+    pub lobby: Arc<Lobby>,
+    server: Arc<Server>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl RunningServer {
+    pub fn join(self) {
+        let _ = self.thread.join();
+    }
+
+    pub fn shutdown(self) {
+        self.server.unblock();
+        let _ = self.thread.join();
+    }
+}
+
+// Serve `lobby` on `bind`, e.g. "127.0.0.1:8080"; port 0 picks a free port
+pub fn serve(bind: &str, lobby: Arc<Lobby>) -> Result<RunningServer, String> {
+    let server = Arc::new(Server::http(bind).map_err(|e| format!("failed to listen on {}: {}", bind, e))?);
+    let addr = server.server_addr().to_ip().ok_or("not an IP address")?;
+    let thread = {
+        let server = Arc::clone(&server);
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle_request(&lobby, request);
+            }
+        })
+    };
+    Ok(RunningServer { addr, lobby, server, thread })
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(serde_json::to_string(body).unwrap())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, ApiError> {
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body).map_err(|e| ApiError(400, e.to_string()))?;
+    // An empty body stands for an empty object
+    let body = if body.trim().is_empty() { "{}" } else { &body };
+    serde_json::from_str(body).map_err(|e| ApiError(400, format!("bad request body: {}", e)))
+}
+
+fn handle_request(lobby: &Arc<Lobby>, mut request: Request) {
+    let path: Vec<String> = request.url().split('?').next().unwrap_or("")
+        .split('/').filter(|part| !part.is_empty()).map(String::from).collect();
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    let method = request.method().clone();
+
+    let result: Result<(u16, serde_json::Value), ApiError> = match (&method, path.as_slice()) {
+        (Method::Get, []) => {
+            let response = Response::from_string(INDEX_HTML)
+                .with_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap());
+            let _ = request.respond(response);
+            return;
+        }
+        (Method::Get, ["games"]) => Ok((200, serde_json::json!(lobby.list()))),
+        (Method::Post, ["games"]) => Ok((201, serde_json::json!(lobby.create()))),
+        (method, ["games", id, rest @ ..]) => match id.parse::<u64>() {
+            Err(_) => Err(not_found("game")),
+            Ok(id) => match (method, rest) {
+                (Method::Get, []) => lobby.view(id).map(|view| (200, serde_json::json!(view))),
+                (Method::Get, ["ws"]) => {
+                    upgrade_websocket(lobby, id, request);
+                    return;
+                }
+                (Method::Post, ["join"]) => parse_body::<JoinRequest>(&mut request)
+                    .and_then(|body| lobby.join(id, body.seat))
+                    .map(|joined| (200, serde_json::json!(joined))),
+                (Method::Post, ["engine"]) => parse_body::<EngineRequest>(&mut request)
+                    .and_then(|body| lobby.attach_engine(id, body.seat, &body.spec))
+                    .map(|view| (200, serde_json::json!(view))),
+                (Method::Post, ["moves"]) => parse_body::<MoveRequest>(&mut request)
+                    .and_then(|body| lobby.submit_move(id, &body.token, &body.mov))
+                    .map(|view| (200, serde_json::json!(view))),
+                _ => Err(not_found("route")),
+            },
+        },
+        _ => Err(not_found("route")),
+    };
+    let response = match result {
+        Ok((status, body)) => json_response(status, &body),
+        Err(ApiError(status, message)) => json_response(status, &serde_json::json!({ "error": message })),
+    };
+    let _ = request.respond(response);
+}
+
+// Switch the connection to WebSocket and forward game states to it until the
+// client goes away. Messages from the client are not read.
+fn upgrade_websocket(lobby: &Lobby, id: u64, request: Request) {
+    let key = request.headers().iter()
+        .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+        .map(|header| header.value.as_str().to_string());
+    let (key, updates) = match (key, lobby.subscribe(id)) {
+        (None, _) => {
+            let _ = request.respond(json_response(400, &serde_json::json!({ "error": "expected a WebSocket handshake" })));
+            return;
+        }
+        (_, Err(ApiError(status, message))) => {
+            let _ = request.respond(json_response(status, &serde_json::json!({ "error": message })));
+            return;
+        }
+        (Some(key), Ok(updates)) => (key, updates),
+    };
+    let response = Response::empty(101)
+        .with_header(Header::from_bytes("Sec-WebSocket-Accept", derive_accept_key(key.as_bytes())).unwrap());
+    let stream = request.upgrade("websocket", response);
+    thread::spawn(move || {
+        let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        for update in updates {
+            if socket.send(Message::Text(update)).is_err() {
+                break;
+            }
+        }
+        let _ = socket.close(None);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    // Minimal HTTP/1.1 client: status and JSON body
+    fn call(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            method, path, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_humans_play_over_http_and_watch_over_websocket() {
+        let server = serve("127.0.0.1:0", Lobby::new(None)).unwrap();
+        let addr = server.addr;
+        let (status, game) = call(addr, "POST", "/games", "");
+        assert_eq!(status, 201);
+        let id = game["id"].as_u64().unwrap();
+
+        let (mut socket, _) = tungstenite::connect(format!("ws://{}/games/{}/ws", addr, id)).unwrap();
+        let mut next_state = || -> GameView {
+            let message = socket.read().unwrap();
+            serde_json::from_str(message.to_text().unwrap()).unwrap()
+        };
+        assert_eq!(next_state().seats, ["open", "open"]);
+
+        let (_, first) = call(addr, "POST", &format!("/games/{}/join", id), "");
+        let (_, second) = call(addr, "POST", &format!("/games/{}/join", id), r#"{"seat": 1}"#);
+        assert_eq!((first["seat"].as_u64(), second["seat"].as_u64()), (Some(0), Some(1)));
+        assert_eq!(call(addr, "POST", &format!("/games/{}/join", id), "").0, 409);
+        next_state();
+        assert_eq!(next_state().seats, ["human", "human"]);
+
+        let play = |token: &serde_json::Value, mov: &str| {
+            call(addr, "POST", &format!("/games/{}/moves", id), &format!(r#"{{"token": {}, "move": "{}"}}"#, token["token"], mov))
+        };
+        assert_eq!(play(&second, "e8").0, 409);  // Not player 1's turn
+        assert_eq!(play(&first, "e5").0, 400);   // Not a legal move
+        assert_eq!(play(&first, "e2").0, 200);
+        assert_eq!(play(&second, "e2h").0, 200);
+        let state = next_state();
+        assert_eq!(state.moves, ["e2"]);
+        assert_eq!(state.pawns[0], "e2");
+        let state = next_state();
+        assert_eq!(state.walls, ["e2h"]);
+        assert_eq!(state.walls_remaining, [10, 9]);
+
+        assert_eq!(call(addr, "GET", "/games/99", "").0, 404);
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_engine_seat_answers_human_moves() {
+        let lobby = Lobby::new(None);
+        let id = lobby.create().id;
+        let human = lobby.join(id, Some(0)).unwrap();
+        assert!(lobby.attach_engine(id, 1, "nonsense").is_err());
+        assert!(lobby.attach_engine(id, 0, "shortest").is_err());  // A human's seat
+        lobby.attach_engine(id, 1, "shortest").unwrap();
+        let updates = lobby.subscribe(id).unwrap();
+
+        lobby.submit_move(id, &human.token, "e2").unwrap();
+        // The engine's reply arrives as a later update
+        let reply = updates.iter()
+            .map(|json| serde_json::from_str::<GameView>(&json).unwrap())
+            .find(|view| view.moves.len() == 2)
+            .unwrap();
+        assert_eq!(reply.moves, ["e2", "e8"]);
+        assert_eq!(reply.to_move, 0);
+
+        // A replaced engine is told to stop searching
+        let stop = match &lobby.games.lock().unwrap()[&id].seats[1] {
+            Seat::Engine { stop, .. } => Arc::clone(stop),
+            _ => unreachable!(),
+        };
+        lobby.attach_engine(id, 1, "random").unwrap();
+        assert!(stop.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_client_engine_specs_are_limited() {
+        let lobby = Lobby::new(None);
+        let id = lobby.create().id;
+        for spec in ["mcts:hash=65536", "mcts:threads=64", "alphabeta:book=/etc/passwd", "network:net=/etc/passwd"] {
+            assert_eq!(lobby.attach_engine(id, 0, spec).unwrap_err().0, 400, "{}", spec);
+        }
+        assert_eq!(lobby.view(id).unwrap().seats, ["open", "open"]);
+
+        let config = client_engine("alphabeta:depth=40,movetime=3600000,playouts=4000000000").unwrap();
+        assert_eq!(config.limits().depth, Some(MAX_CLIENT_DEPTH));
+        assert_eq!(config.limits().move_time, Some(MAX_CLIENT_MOVE_TIME));
+        assert_eq!(config.limits().playouts, Some(MAX_CLIENT_PLAYOUTS));
+
+        // An engine that fails to build is reported back rather than left waiting
+        let config = EngineConfig { book: Some("no-such-book.json".to_string()), ..EngineConfig::parse("random").unwrap() };
+        let (built_sender, built) = mpsc::channel();
+        let (_requests, receiver) = mpsc::channel();
+        engine_worker(Arc::clone(&lobby), id, 0, 0, config, built_sender, receiver);
+        assert!(built.recv().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_finished_games_are_removed() {
+        let lobby = Lobby::new(None);
+        let id = lobby.create().id;
+        let updates = lobby.subscribe(id).unwrap();
+        lobby.attach_engine(id, 0, "shortest").unwrap();
+        lobby.attach_engine(id, 1, "oneply").unwrap();
+
+        // Updates end when the finished game is dropped
+        let last = updates.iter().last().map(|json| serde_json::from_str::<GameView>(&json).unwrap()).unwrap();
+        assert!(last.finished);
+        assert_eq!(lobby.view(id).unwrap_err().0, 404);
+        assert!(lobby.list().is_empty());
+    }
+}
@@ -6,6 +6,32 @@ pub mod quorridor;
 pub mod mcts_impl;
 pub mod policy_network;
 pub mod metrics_logger;
+pub mod zobrist;
+pub mod rollout;
+pub mod move_filter;
+pub mod tree_reuse;
+pub mod time_control;
+pub mod transposition;
+pub mod search;
+pub mod ponder;
+pub mod notation;
+pub mod search_result;
+pub mod alphabeta;
+pub mod engine;
+pub mod bots;
+pub mod engine_config;
+pub mod game_record;
+pub mod elo;
+pub mod ratings;
+pub mod opening_book;
+pub mod endgame;
+pub mod protocol;
+pub mod game_server;
+pub mod tui;
+pub mod path_analysis;
+pub mod board_svg;
+pub mod replay;
+pub mod analysis;
 
 // Tests are currently out of date with the grid refactoring
 // #[cfg(test)]
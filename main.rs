@@ -1,14 +1,16 @@
 use mcts::*;
-use mcts::tree_policy::*;
-use mcts::transposition_table::*;
 
 use itertools::iproduct;
 use std::io::{self, Write};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use quorridor::{Quorridor, Move, Piece, Wall, mcts_impl::MyEvaluator, move_player, place_wall, Orientation, WallPlacementResult, GRID_HEIGHT, policy_network::PolicyNetwork, log_game_metrics, create_metrics_file};
+use quorridor::{Quorridor, Move, Piece, Wall, mcts_impl::MyEvaluator, engine::{Engine, MctsEngine, create_engine, DEFAULT_EXPLORATION}, engine_config::EngineConfig, transposition::DEFAULT_TABLE_MB, time_control::{GameClock, SearchLimits}, move_player, place_wall, Orientation, WallPlacementResult, policy_network::PolicyNetwork, rollout::RolloutConfig, move_filter::WallFilter, opening_book::{OpeningBook, BookEngine, DEFAULT_BOOK_DEPTH}, endgame::EndgameSolver, notation::move_to_notation, search_result::SearchResult, quorridor::shortest_path_to_goal, tui::Tui, replay::{self, Replay}, game_record::load_records, path_analysis::{PathOverlay, path_overlay, wall_impacts}, log_game_metrics, create_metrics_file};
  
-fn display_board(game: &Quorridor) {
+// `overlay` marks squares on player 0's shortest route with '.', player 1's
+// with ':', both with '*', and critical edges with '!'
+fn display_board(game: &Quorridor, overlay: Option<&PathOverlay>) {
     let mut board = [
         ["+", "---", "+", "---", "+", "---", "+", "---", "+", "---", "+", "---", "+", "---", "+", "---", "+", "---", "+", "  0"],
         ["|", "   ", "|", "   ", "|", "   ", "|", "   ", "|", "   ", "|", "   ", "|", "   ", "|", "   ", "|", "   ", "|", "   "],
@@ -39,54 +41,102 @@ fn display_board(game: &Quorridor) {
             }
         }
     }
+    if let Some(overlay) = overlay {
+        for (player, route) in overlay.routes.iter().enumerate() {
+            for &(x, y) in route {
+                let cell = &mut board[y as usize][x as usize];
+                *cell = if *cell == "   " { if player == 0 { " . " } else { " : " } } else { " * " };
+            }
+        }
+        for &(x, y) in overlay.critical.iter().flatten() {
+            let cell = &mut board[y as usize][x as usize];
+            *cell = if cell.len() == 3 { " ! " } else { "!" };
+        }
+    }
     for (idx, piece) in game.player_pieces.iter().enumerate() {
-        board[piece.y as usize][piece.x as usize] = if idx == 0 { " A " } else { " H " };
+        board[piece.y as usize][piece.x as usize] = if idx == 0 { " 0 " } else { " 1 " };
     }
     for row in &board {
         println!("{}", row.concat());
     }        //return false;
 }
 
-fn get_ai_move(game: &Quorridor, evaluator: &MyEvaluator, use_parallel: bool) -> Move {
+// Plies searched on top of the race tables while only one side has walls
+const ENDGAME_SEARCH_DEPTH: u32 = 2;
+
+fn search_threads(use_parallel: bool) -> usize {
+    if use_parallel { 4 } else { 1 }
+}
+
+fn get_ai_move(engine: &mut dyn Engine, limits: &SearchLimits, json_output: bool) -> (Move, SearchResult) {
     println!("\nAI is thinking...");
     
-    let mut mcts = MCTSManager::new(
-        game.clone(), 
-        quorridor::mcts_impl::MyMCTS, 
-        evaluator.clone(),
-        UCTPolicy::new(0.5),  // Lower value = more exploitation (picks highest scoring moves)
-        ApproxTable::new(8192)
-    );
-    
-    if use_parallel {
-        mcts.playout_n_parallel(1000, 4);  // Parallel mode (heuristic only)
+    let result = engine.search(limits);
+    if json_output {
+        println!("{}", result.to_json());
     } else {
-        mcts.playout_n(10000);  // Single-threaded (for network evaluation)
+        println!("\n{}", result);
     }
-   
-    //quorridor::mcts_impl::print_stats();
-    
-    println!("\nEvaluation of moves:");
-    mcts.tree().debug_moves();
 
-    match mcts.best_move() {
+    match result.best_move.clone() {
         Some(mov) => {
-            println!("AI chose: {:?}", mov);
-            mov
+            println!("AI chose: {} ({:?})", result.best_move_notation.as_deref().unwrap_or("?"), mov);
+            (mov, result)
         },
         None => panic!("No moves available for AI!"),
     }
 }
 
 
-fn capture_input() -> Option<Move> {
+// The value after `flag`, if given, parsed as T
+fn number_arg<T: std::str::FromStr>(args: &[String], flag: &str) -> Result<Option<T>, String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.parse().map_err(|_| format!("{} takes a whole number, got '{}'", flag, v)))
+        .transpose()
+}
+
+// --hash and --depth, with their defaults
+fn whole_number_args(args: &[String]) -> Result<(usize, Option<u32>), String> {
+    Ok((
+        number_arg(args, "--hash")?.unwrap_or(DEFAULT_TABLE_MB),
+        number_arg(args, "--depth")?,
+    ))
+}
+
+// The value after `flag`, if given, as a duration in units of `unit` seconds
+fn duration_arg(args: &[String], flag: &str, unit: f64) -> Result<Option<Duration>, String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.parse::<f64>().ok()
+            .and_then(|n| Duration::try_from_secs_f64(n * unit).ok())
+            .ok_or_else(|| format!("{} takes a non-negative number, got '{}'", flag, v)))
+        .transpose()
+}
+
+// --movetime in milliseconds, and --clock with --inc in seconds
+fn time_args(args: &[String]) -> Result<(Option<Duration>, Option<GameClock>), String> {
+    let move_time = duration_arg(args, "--movetime", 0.001)?;
+    let increment = duration_arg(args, "--inc", 1.0)?.unwrap_or(Duration::ZERO);
+    let clock = duration_arg(args, "--clock", 1.0)?.map(|total| GameClock::new(total, increment));
+    Ok((move_time, clock))
+}
+
+fn capture_input() -> String {
     print!("> ");
     io::stdout().flush().unwrap();
     
     let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    let input = input.trim();
-    
+    // End of input, e.g. a piped script ran out
+    if io::stdin().read_line(&mut input).unwrap() == 0 {
+        std::process::exit(0);
+    }
+    input.trim().to_string()
+}
+
+fn parse_input(input: &str) -> Option<Move> {
     let mov = match input {
         "u" => Some(Move::Up),
         "d" => Some(Move::Down),
@@ -135,9 +185,87 @@ fn capture_input() -> Option<Move> {
 }
 
 
-fn get_human_move(game: &Quorridor) -> Move {
+// The engine behind the `hint` command, built the first time a hint is asked for
+struct Hints {
+    config: EngineConfig,
+    engine: Option<Box<dyn Engine>>,
+}
+
+// Moves listed by `hint`, and the engine that finds them unless --hint says otherwise
+const HINT_MOVES: usize = 3;
+const DEFAULT_HINT_ENGINE: &str = "mcts:playouts=2000";
+
+impl Hints {
+    fn print(&mut self, start: &Quorridor, history: &[Move]) {
+        if self.engine.is_none() {
+            match self.config.build() {
+                Ok(engine) => self.engine = Some(engine),
+                Err(e) => {
+                    println!("No hints: {}", e);
+                    return;
+                }
+            }
+        }
+        let engine = self.engine.as_mut().unwrap();
+        engine.set_position(start, history);
+        let result = engine.search(&self.config.limits());
+        let mut top: Vec<String> = result.root_moves.iter()
+            .take(HINT_MOVES)
+            .map(|m| match m.win_probability {
+                Some(p) => format!("{} ({:.1}%)", m.notation, p * 100.0),
+                None => m.notation.clone(),
+            })
+            .collect();
+        // Engines that pick a move without searching only know their choice
+        if top.is_empty() && let Some(best) = &result.best_move_notation {
+            top.push(best.clone());
+        }
+        println!("Hint: {}", top.join(", "));
+    }
+}
+
+// The AI's last move and the search behind it, for the `why` command
+struct AiMove {
+    before: Quorridor,
+    mov: Move,
+    result: SearchResult,
+}
+
+fn print_why(ai_move: Option<&AiMove>) {
+    let Some(AiMove { before, mov, result }) = ai_move else {
+        println!("The AI has not moved yet");
+        return;
+    };
+    let mut after = before.clone();
+    after.make_move(mov);
+    let ai = before.active_player;
+    println!("AI played {} expecting to win {:.1}%", move_to_notation(before, mov), result.win_probability * 100.0);
+    if !result.principal_variation_notation.is_empty() {
+        println!("  Expected line: {}", result.principal_variation_notation.join(" "));
+    }
+    let paths = [before, &after].map(|game| [0, 1].map(|player| shortest_path_to_goal(game, player)));
+    for (player, (old, new)) in paths[0].into_iter().zip(paths[1]).enumerate() {
+        let change = match (old, new) {
+            (Some(old), Some(new)) => format!("{} -> {} ({:+})", old, new, new as i64 - old as i64),
+            _ => "-".to_string(),
+        };
+        println!("  Shortest path of player {}{}: {}", player, if player == ai { " (AI)" } else { "" }, change);
+    }
+    if let (Move::PlaceWall(..), [Some(own_old), Some(their_old)], [Some(own_new), Some(their_new)]) =
+        (mov, [paths[0][ai], paths[0][1 - ai]], [paths[1][ai], paths[1][1 - ai]]) {
+        let gain = (their_new as i64 - their_old as i64) - (own_new as i64 - own_old as i64);
+        if gain > 0 {
+            println!("  The wall gains the AI {} step(s) in the race", gain);
+        } else {
+            println!("  The wall does not gain the AI steps by itself; see the expected line");
+        }
+    }
+}
+
+fn get_human_move(game: &Quorridor, start: &Quorridor, history: &[Move], hints: &mut Hints, last_ai_move: Option<&AiMove>) -> Move {
     
-    let available = game.available_moves();
+    // Walls that would cut a player off from their goal are not allowed
+    let available = game.legal_moves();
     
     println!("\\nYour turn! Available moves:");
     println!("  u - Up");
@@ -146,8 +274,22 @@ fn get_human_move(game: &Quorridor) -> Move {
     println!("  r - Right");
     println!("  w x y h - Place horizontal wall at (x, y) where x,y are 0-9");
     println!("  w x y v - Place vertical wall at (x, y) where x,y are 0-9");
+    println!("  hint - The engine's top {} moves with their win probability", HINT_MOVES);
+    println!("  why - Explain the AI's last move");
     loop {
-        let mov = capture_input();
+        let input = capture_input();
+        match input.as_str() {
+            "hint" => {
+                hints.print(start, history);
+                continue;
+            }
+            "why" => {
+                print_why(last_ai_move);
+                continue;
+            }
+            _ => {}
+        }
+        let mov = parse_input(&input);
         
         if let Some(mov) = mov {
             // Validate move is legal
@@ -163,10 +305,93 @@ fn get_human_move(game: &Quorridor) -> Move {
 
 
 
+// Who plays a side: a human at the keyboard or an engine searching within `limits`
+// (the clock, when there is one, is added on each move)
+enum Seat {
+    Human,
+    Engine { engine: Box<dyn Engine>, limits: SearchLimits },
+}
+
+impl Seat {
+    fn describe(&self) -> String {
+        match self {
+            Seat::Human => "human".to_string(),
+            Seat::Engine { engine, .. } => engine.name(),
+        }
+    }
+}
+
+// Walls the side to move could place that change a distance, best first
+fn print_wall_impacts(game: &Quorridor) {
+    let impacts: Vec<String> = wall_impacts(game).iter()
+        .take(5)
+        .map(|impact| format!("{} ({:+}/{:+})", impact.notation, impact.delta[0], impact.delta[1]))
+        .collect();
+    if !impacts.is_empty() {
+        println!("Walls for player {} (change in distance P0/P1): {}", game.active_player, impacts.join(", "));
+    }
+}
+
+// A line for the player: the TUI's message panel, or stdout without one
+fn report(tui: &mut Option<Tui>, text: String) {
+    match tui {
+        Some(tui) => tui.message(text),
+        None => println!("{}", text),
+    }
+}
+
+// Load game `game_number` (from 1) of a record file, evaluate every position
+// and let the user step through it
+fn run_replay(path: &str, game_number: usize, engine: &mut dyn Engine, limits: &SearchLimits) -> Result<(), String> {
+    let records = load_records(path)?;
+    let record = records.get(game_number.wrapping_sub(1)).cloned()
+        .ok_or_else(|| format!("{} has {} games, no game {}", path, records.len(), game_number))?;
+    let mut replay = Replay::new(record)?;
+    let mut tui = Tui::enter(replay.record.players.clone()).map_err(|e| format!("Could not start the terminal UI: {}", e))?;
+    if replay::evaluate_all(&mut tui, &mut replay, engine, limits).map_err(|e| e.to_string())? {
+        replay::browse(&mut tui, &mut replay).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let use_network = args.contains(&"--network".to_string());
+    let use_rollout = args.contains(&"--rollout".to_string());
+    let engine_name = args.iter().position(|a| a == "--engine")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let use_ponder = args.contains(&"--ponder".to_string());
+    let json_output = args.contains(&"--json".to_string());
     let log_metrics = args.contains(&"--log-metrics".to_string());
+    let prune_walls = args.contains(&"--prune-walls".to_string());
+    let use_tui = args.contains(&"--tui".to_string());
+    let show_paths = args.contains(&"--paths".to_string());
+    let (table_mb, search_depth) = match whole_number_args(&args) {
+        Ok(numbers) => numbers,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let (move_time, mut clock) = match time_args(&args) {
+        Ok(times) => times,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    // --p0/--p1 take "human" or an engine spec with that seat's own settings,
+    // e.g. --p0 mcts:playouts=5000 --p1 network:net=gen3.safetensors
+    let seat_args = ["--p0", "--p1"].map(|flag| args.iter().position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned());
+    // These build player 0's default engine; a --p0 spec carries its own settings
+    let default_engine_flags = ["--engine", "--network", "--rollout", "--prune-walls", "--book", "--book-depth", "--hash"];
+    if seat_args[0].is_some() && let Some(flag) = default_engine_flags.iter().find(|flag| args.contains(&flag.to_string())) {
+        eprintln!("{} does not apply with --p0; give player 0's settings in its spec, e.g. --p0 mcts:hash=128,prune=true", flag);
+        return;
+    }
     
     // Create metrics file if logging is enabled
     if log_metrics {
@@ -189,25 +414,202 @@ fn main() {
                 (MyEvaluator::new(), true)
             }
         }
+    } else if use_rollout {
+        (MyEvaluator::with_rollouts(RolloutConfig::default()), true)
     } else {
         (MyEvaluator::new(), true)
     };
+    let evaluator = evaluator.with_wall_filter(prune_walls.then(WallFilter::default));
+    
+    let threads = search_threads(use_parallel);
+    let default_playouts = if use_parallel { 1000 } else { 10000 };
+    // Limits for engines whose spec sets none
+    let shared_limits = SearchLimits {
+        playouts: if move_time.is_none() && clock.is_none() && search_depth.is_none() { Some(default_playouts) } else { None },
+        move_time,
+        clock: None,
+        depth: search_depth,
+    };
     
-    let mut game = Quorridor::default();
+    // The engine of the AI seat left at its default, configured by the flags above.
+    // --engine picks another backend; the evaluator flags configure the default MCTS.
+    let mut default_engine: Option<Box<dyn Engine>> = None;
+    if seat_args[0].is_none() {
+        let mut engine: Box<dyn Engine> = match engine_name.as_deref() {
+            None | Some("mcts") => Box::new(MctsEngine::new(evaluator, DEFAULT_EXPLORATION, threads, table_mb)),
+            Some(name) => match create_engine(name, threads, table_mb) {
+                Ok(engine) => engine,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            },
+        };
+        
+        // --book plays the opening from a book built with the book binary
+        let book_path = args.iter().position(|a| a == "--book")
+            .and_then(|i| args.get(i + 1))
+            .cloned();
+        if let Some(path) = &book_path {
+            match OpeningBook::load(path) {
+                Ok(book) => {
+                    let book_depth = match number_arg(&args, "--book-depth") {
+                        Ok(depth) => depth.unwrap_or(DEFAULT_BOOK_DEPTH),
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    println!("Opening book: {} positions, used for the first {} plies", book.positions.len(), book_depth);
+                    engine = Box::new(BookEngine::new(engine, Arc::new(book), book_depth));
+                }
+                Err(e) => eprintln!("Could not load opening book: {}", e),
+            }
+        }
+        default_engine = Some(engine);
+    }
+    
+    // Player 0 defaults to the AI above and player 1 to a human
+    let mut seats: Vec<Seat> = Vec::new();
+    for (player, seat_arg) in seat_args.iter().enumerate() {
+        let seat = match seat_arg.as_deref() {
+            None if player == 0 => Seat::Engine { engine: default_engine.take().unwrap(), limits: shared_limits.clone() },
+            None | Some("human") => Seat::Human,
+            Some(spec) => {
+                let config = match EngineConfig::parse(spec) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        eprintln!("--p{}: {}", player, e);
+                        return;
+                    }
+                };
+                let engine = match config.build() {
+                    Ok(engine) => engine,
+                    Err(e) => {
+                        eprintln!("--p{}: {}", player, e);
+                        return;
+                    }
+                };
+                let own = config.limits();
+                let limits = if own.playouts.is_none() && own.move_time.is_none() && own.depth.is_none() { shared_limits.clone() } else { own };
+                Seat::Engine { engine, limits }
+            }
+        };
+        seats.push(seat);
+    }
+    // --replay FILE reviews a stored game with player 0's engine instead of playing one
+    let replay_path = args.iter().position(|a| a == "--replay")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    if let Some(path) = replay_path {
+        let Seat::Engine { engine, limits } = &mut seats[0] else {
+            eprintln!("--replay evaluates with player 0's engine; --p0 must not be human");
+            return;
+        };
+        let game_number = match number_arg(&args, "--game") {
+            Ok(number) => number.unwrap_or(1),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = run_replay(&path, game_number, engine.as_mut(), limits) {
+            eprintln!("{}", e);
+        }
+        return;
+    }
+    let watching = seats.iter().all(|seat| matches!(seat, Seat::Engine { .. }));
+    // --hint sets the engine that answers the human's `hint` command. The
+    // terminal UI reads moves from the keyboard and has no hint or why commands;
+    // its panel shows each engine search instead.
+    if use_tui && args.contains(&"--hint".to_string()) {
+        eprintln!("--hint does not apply with --tui, which has no hint command");
+        std::process::exit(1);
+    }
+    let hint_spec = args.iter().position(|a| a == "--hint")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_HINT_ENGINE.to_string());
+    let mut hints = match EngineConfig::parse(&hint_spec) {
+        Ok(config) => Hints { config, engine: None },
+        Err(e) => {
+            eprintln!("--hint: {}", e);
+            return;
+        }
+    };
+    // Pause before each AI move so a game between two engines can be followed
+    let delay = match duration_arg(&args, "--delay", 0.001) {
+        Ok(delay) => delay.unwrap_or(Duration::from_millis(if watching { 1000 } else { 0 })),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    let start = Quorridor::default();
+    let mut game = start.clone();
+    let mut history: Vec<Move> = Vec::new();
+    let solver = EndgameSolver::new();
+    let mut last_ai_move: Option<AiMove> = None;  // For `why`, so only kept outside the terminal UI
+    let is_mcts = engine_name.is_none() || engine_name.as_deref() == Some("mcts");
     
     println!("=== Quorridor ===");
-    println!("Player 0 (A) starts at bottom, needs to reach top (y=8)");
-    println!("Player 1 (H - you) starts at top, needs to reach bottom (y=0)");
+    println!("Player 0 starts at the bottom and needs to reach the top (y=8): {}", seats[0].describe());
+    println!("Player 1 starts at the top and needs to reach the bottom (y=0): {}", seats[1].describe());
     println!("Wall placement: Use coordinates 0-6 (e.g., 'w 4 3 h' for horizontal wall)");
-    if use_network {
+    if seat_args[0].is_some() {
+        // Both seats were configured explicitly
+    } else if !is_mcts {
+        println!("AI Mode: {}", seats[0].describe());
+    } else if use_network {
         println!("AI Mode: Trained Neural Network (single-threaded MCTS)");
+    } else if use_rollout {
+        println!("AI Mode: Random Rollouts (parallel MCTS)");
     } else {
         println!("AI Mode: Path Distance Heuristic (parallel MCTS)");
     }
+    if prune_walls {
+        println!("Wall filtering: only walls near a pawn or a shortest path are searched");
+    }
+    if let Some(clock) = &clock {
+        println!("Clock: {:.0}s + {:.1}s per move", clock.remaining(0).as_secs_f64(), clock.state(0).increment.as_secs_f64());
+    }
+    if let Some(move_time) = move_time {
+        println!("AI move time: {}ms", move_time.as_millis());
+    }
+    if !delay.is_zero() {
+        println!("AI moves are shown {}ms apart", delay.as_millis());
+    }
+    if use_ponder {
+        println!("Pondering: AI keeps thinking on your time");
+    }
     println!();
     
+    // --tui takes over the terminal; the messages above scroll away behind it
+    let mut tui = if use_tui {
+        match Tui::enter([seats[0].describe(), seats[1].describe()]) {
+            Ok(mut tui) => {
+                tui.info.show_paths = show_paths;
+                Some(tui)
+            }
+            Err(e) => {
+                eprintln!("Could not start the terminal UI: {}", e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+    
     loop {
-        display_board(&game);
+        match tui.as_mut() {
+            Some(tui) => tui.draw(&game, None).expect("terminal output failed"),
+            None if show_paths => {
+                display_board(&game, Some(&path_overlay(&game)));
+                print_wall_impacts(&game);
+            }
+            None => display_board(&game, None),
+        }
         
         // Log metrics if enabled
         if log_metrics {
@@ -215,22 +617,94 @@ fn main() {
         }
         
         // Check for winner
-        if game.game_over() {
-            if game.player_pieces[0].y >= (GRID_HEIGHT - 2) as i64 {
-                println!("Player 0 (A) wins!");
-            } else {
-                println!("Player 1 (H) wins!");
+        if let Some(winner) = game.winner() {
+            report(&mut tui, format!("Player {} ({}) wins!", winner, seats[winner].describe()));
+            if let Some(tui) = tui.as_mut() {
+                tui.message("Press any key to exit");
+                tui.draw(&game, None).expect("terminal output failed");
+                tui.wait_for_key().expect("terminal input failed");
             }
             break;
         }
-        let mov = if game.active_player == 0 {
-            get_ai_move(&game, &evaluator, use_parallel)
-        } else {
-            get_human_move(&game)
+        let player = game.active_player;
+        // Only a race can be solved: no walls left, or one side out of them. With
+        // walls still in hand a faster win may exist than the one found.
+        if game.walls_remaining.contains(&0) && let Some(result) = solver.solve(&game, ENDGAME_SEARCH_DEPTH) {
+            let bound = if game.walls_remaining == [0, 0] { "" } else { " at most" };
+            report(&mut tui, format!("Endgame solved, player {} to move: {}{}", player, result, bound));
+        }
+        
+        // Engines are timed from after the display delay, humans from here
+        let mut move_start = Instant::now();
+        let mov = match &mut seats[player] {
+            Seat::Engine { engine, limits } => {
+                let limits = SearchLimits { clock: clock.as_ref().map(|c| c.state(player)), ..limits.clone() };
+                engine.set_position(&start, &history);
+                match tui.as_mut() {
+                    Some(tui) => {
+                        if tui.pause(delay).expect("terminal input failed") {
+                            break;
+                        }
+                        move_start = Instant::now();
+                        tui.message(format!("Player {} is thinking...", player));
+                        tui.draw(&game, None).expect("terminal output failed");
+                        let result = engine.search(&limits);
+                        let Some(mov) = result.best_move.clone() else {
+                            tui.message(format!("Player {} has no move", player));
+                            break;
+                        };
+                        tui.info.search = Some(result);
+                        mov
+                    }
+                    None => {
+                        thread::sleep(delay);
+                        move_start = Instant::now();
+                        let (mov, result) = get_ai_move(engine.as_mut(), &limits, json_output);
+                        last_ai_move = Some(AiMove { before: game.clone(), mov: mov.clone(), result });
+                        mov
+                    }
+                }
+            }
+            Seat::Human => {
+                // An engine opponent keeps searching while the human thinks; its next
+                // set_position keeps the subtree of their move
+                if use_ponder && let Seat::Engine { engine, .. } = &mut seats[1 - player] {
+                    engine.set_position(&start, &history);
+                    engine.start_ponder();
+                }
+                let mov = match tui.as_mut() {
+                    Some(tui) => match tui.read_move(&game).expect("terminal input failed") {
+                        Some(mov) => mov,
+                        None => break,
+                    },
+                    None => {
+                        if seats.iter().all(|seat| matches!(seat, Seat::Human)) {
+                            println!("\nPlayer {} to move", player);
+                        }
+                        get_human_move(&game, &start, &history, &mut hints, last_ai_move.as_ref())
+                    }
+                };
+                if let Seat::Engine { engine, .. } = &mut seats[1 - player]
+                    && let Some(stats) = engine.stop_ponder() {
+                    report(&mut tui, format!("Pondered {} playouts ({} nodes) in {:.1}s", stats.playouts, stats.nodes, stats.elapsed.as_secs_f64()));
+                }
+                mov
+            }
         };
         
+        if let Some(clock) = clock.as_mut() {
+            clock.record_move(player, move_start.elapsed());
+            report(&mut tui, format!("Clock: P0 {:.1}s | P1 {:.1}s", clock.remaining(0).as_secs_f64(), clock.remaining(1).as_secs_f64()));
+            if clock.flagged(player) {
+                report(&mut tui, format!("Player {} ran out of time!", player));
+                break;
+            }
+        }
+        
+        if let Some(tui) = tui.as_mut() {
+            tui.info.moves.push(move_to_notation(&game, &mov));
+        }
         game.make_move(&mov);
+        history.push(mov);
     }
 }
-
- 
@@ -2,45 +2,236 @@ use mcts::*;
 use mcts::tree_policy::*;
 use mcts::transposition_table::*;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU32, Ordering};
 
-use crate::quorridor::{Quorridor, shortest_path_to_goal, GRID_WIDTH, GRID_HEIGHT};
+use crate::quorridor::{Quorridor, shortest_path_to_goal, GRID_HEIGHT};
 use crate::moves::Move;
 use crate::policy_network::PolicyNetwork;
+use crate::rollout::{RolloutConfig, rollout_value};
+use crate::tree_reuse::InheritedTree;
+use crate::transposition::QuorridorTable;
+use crate::move_filter::WallFilter;
+use crate::endgame::{EndgameSolver, Proven};
 
 impl TranspositionHash for Quorridor {
     fn hash(&self) -> u64 {
-        let mut hash: u64 = 0;
-        
-        hash ^= self.active_player as u64;
-        // Hash both players' positions
-        hash = hash.wrapping_mul(31).wrapping_add(((self.player_pieces[0].x as u64) << 32) | (self.player_pieces[0].y as u64));
-        hash = hash.wrapping_mul(31).wrapping_add(((self.player_pieces[1].x as u64) << 32) | (self.player_pieces[1].y as u64));
-        
-        // Hash the grid (only even positions where walls can be)
-        for y in (0..GRID_HEIGHT).step_by(2) {
-            for x in (0..GRID_WIDTH).step_by(2) {
-                if self.grid[y][x] {
-                    hash = hash.wrapping_mul(31).wrapping_add((x as u64) << 8 | (y as u64));
-                }
-            }
+        // The table treats 0 as an empty slot
+        self.zobrist.max(1)
+    }
+}
+ 
+// Per-edge data fixed at expansion: statistics inherited from the previous search,
+// whether the wall filter left the move out of selection, and the child's result
+// when it is known without searching it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MovePrior {
+    pub visits: u64,
+    pub sum_rewards: i64,
+    pub pruned: bool,
+    pub proven: Option<Proven>,  // For the side to move in the child
+}
+
+// Proven result of a node for its side to move, worked out from its children
+// during backpropagation (MCTS-Solver)
+#[derive(Debug, Default)]
+pub struct SolverNode {
+    result: AtomicU32,  // 0 unknown, 2n + 1 a win in n plies, 2n + 2 a loss in n plies
+}
+
+impl SolverNode {
+    pub fn get(&self) -> Option<Proven> {
+        match self.result.load(Ordering::Relaxed) {
+            0 => None,
+            code if code % 2 == 1 => Some(Proven::Win(code / 2)),
+            code => Some(Proven::Loss(code / 2 - 1)),
         }
+    }
 
-        hash
+    fn set(&self, result: Proven) {
+        let code = match result {
+            Proven::Win(plies) => 2 * plies + 1,
+            Proven::Loss(plies) => 2 * plies + 2,
+        };
+        self.result.store(code, Ordering::Relaxed);
     }
 }
- 
+
+// Result of the position after `info`'s move, for the side to move there
+pub fn child_result(info: &MoveInfo<MyMCTS>) -> Option<Proven> {
+    info.move_evaluation().proven.or_else(|| {
+        let child = info.child()?;
+        // An expanded child with no moves is a finished game
+        child.data().get().or_else(|| child.moves().next().is_none().then_some(Proven::Loss(0)))
+    })
+}
+
+// A node is won if any move leads to a lost child, and lost once every move
+// leads to a won one. Unexpanded and unproven moves leave it open.
+pub fn solve_from_children<'a>(moves: impl Iterator<Item = &'a MoveInfo<MyMCTS>>) -> Option<Proven> {
+    let mut fastest_win: Option<u32> = None;
+    let mut slowest_loss: Option<u32> = None;
+    let mut all_lost = true;
+    for info in moves {
+        match child_result(info).map(Proven::for_parent) {
+            Some(Proven::Win(plies)) => fastest_win = Some(fastest_win.map_or(plies, |best| best.min(plies))),
+            Some(Proven::Loss(plies)) => slowest_loss = Some(slowest_loss.map_or(plies, |worst| worst.max(plies))),
+            None => all_lost = false,
+        }
+    }
+    match (fastest_win, slowest_loss) {
+        (Some(plies), _) => Some(Proven::Win(plies)),
+        (None, Some(plies)) if all_lost => Some(Proven::Loss(plies)),
+        _ => None,
+    }
+}
+
+// UCT that treats inherited edge statistics as if they had been searched in this tree
+#[derive(Clone, Debug)]
+pub struct PriorUCTPolicy {
+    exploration_constant: f64,
+}
+
+impl PriorUCTPolicy {
+    pub fn new(exploration_constant: f64) -> Self {
+        assert!(exploration_constant > 0.0, "exploration constant must be positive");
+        PriorUCTPolicy { exploration_constant }
+    }
+
+    pub fn exploration_constant(&self) -> f64 {
+        self.exploration_constant
+    }
+
+    // UCT value of an edge from its searched and inherited statistics combined;
+    // `ln_total` is the log of the parent's visits, counted once
+    fn edge_score(&self, visits: u64, sum_rewards: i64, ln_total: f64) -> f64 {
+        if visits == 0 {
+            return f64::INFINITY;
+        }
+        let explore_term = 2.0 * (ln_total / visits as f64).sqrt();
+        let mean_action_value = sum_rewards as f64 / visits as f64;
+        self.exploration_constant * explore_term + mean_action_value
+    }
+}
+
+impl TreePolicy<MyMCTS> for PriorUCTPolicy {
+    type MoveEvaluation = MovePrior;
+    type ThreadLocalData = ();
+
+    fn choose_child<'a, MoveIter>(&self, moves: MoveIter, _: SearchHandle<MyMCTS>) -> &'a MoveInfo<MyMCTS>
+        where MoveIter: Iterator<Item = &'a MoveInfo<MyMCTS>> + Clone
+    {
+        let total_visits = moves.clone().map(|m| m.visits() + m.move_evaluation().visits).sum::<u64>() + 1;
+        let ln_total = (total_visits as f64).ln();
+
+        let score = |m: &MoveInfo<MyMCTS>| -> f64 {
+            // Decided moves are taken or avoided outright
+            match child_result(m) {
+                Some(Proven::Loss(_)) => return f64::INFINITY,
+                Some(Proven::Win(_)) => return f64::NEG_INFINITY,
+                None => {}
+            }
+            let prior = m.move_evaluation();
+            if prior.pruned {
+                return f64::NEG_INFINITY;
+            }
+            self.edge_score(m.visits() + prior.visits, m.sum_rewards() + prior.sum_rewards, ln_total)
+        };
+
+        moves.max_by(|a, b| score(a).total_cmp(&score(b))).unwrap()
+    }
+}
+
+// Proven results score like terminal positions, less one per ply so faster wins rank higher
+fn proven_score(result: Proven, side_to_move: usize) -> i64 {
+    let score = 100000 - result.plies() as i64;
+    if result.winner(side_to_move) == 0 { score } else { -score }
+}
+
 #[derive(Clone)]
 pub struct MyEvaluator {
     network: Option<Arc<Mutex<PolicyNetwork>>>,
+    rollout: Option<RolloutConfig>,
+    inherited: Option<Arc<InheritedTree>>,
+    wall_filter: Option<WallFilter>,
+    solver: Option<Arc<EndgameSolver>>,
 }
 
 impl MyEvaluator {
     pub fn new() -> Self {
-        MyEvaluator { network: None }
+        MyEvaluator { network: None, rollout: None, inherited: None, wall_filter: None, solver: Some(Arc::new(EndgameSolver::new())) }
     }
     
     pub fn with_network(network: Arc<Mutex<PolicyNetwork>>) -> Self {
-        MyEvaluator { network: Some(network) }
+        MyEvaluator { network: Some(network), ..MyEvaluator::new() }
+    }
+
+    pub fn with_rollouts(config: RolloutConfig) -> Self {
+        MyEvaluator { rollout: Some(config), ..MyEvaluator::new() }
+    }
+
+    // Same evaluator, seeding new nodes with statistics kept from the previous search
+    pub fn with_inherited(&self, inherited: Option<Arc<InheritedTree>>) -> Self {
+        MyEvaluator { inherited, ..self.clone() }
+    }
+
+    // Only search the wall placements `filter` keeps; pawn moves are never pruned
+    pub fn with_wall_filter(&self, filter: Option<WallFilter>) -> Self {
+        MyEvaluator { wall_filter: filter, ..self.clone() }
+    }
+
+    // Whether wall-less races are looked up exactly rather than searched; on by default
+    pub fn with_endgame_solver(&self, enabled: bool) -> Self {
+        let solver = if enabled { self.solver.clone().or_else(|| Some(Arc::new(EndgameSolver::new()))) } else { None };
+        MyEvaluator { solver, ..self.clone() }
+    }
+
+    fn move_priors(&self, state: &Quorridor, moves: &[Move]) -> Vec<MovePrior> {
+        let mut priors = self.inherited_priors(state, moves);
+        for (prior, mov) in priors.iter_mut().zip(moves) {
+            prior.proven = self.known_result(state, mov);
+        }
+        if let Some(filter) = &self.wall_filter {
+            let keep = filter.keep_mask(state, moves, &mut rand::thread_rng());
+            for (prior, keep) in priors.iter_mut().zip(keep) {
+                // A move the previous search already visited stays searchable
+                prior.pruned = !keep && prior.visits == 0;
+            }
+        }
+        // The mcts crate expands available_moves, which still holds walls that cut off a goal
+        for (prior, blocks) in priors.iter_mut().zip(state.path_blocking_walls(moves)) {
+            prior.pruned |= blocks;
+        }
+        priors
+    }
+
+    // The child's result where it costs no search: a pawn move onto the goal
+    // row, or any move in a wall-less race
+    fn known_result(&self, state: &Quorridor, mov: &Move) -> Option<Proven> {
+        if matches!(mov, Move::PlaceWall(..)) {
+            return None;
+        }
+        let mut child = state.clone();
+        child.make_move(mov);
+        if child.game_over() {
+            return Some(Proven::Loss(0));
+        }
+        match &self.solver {
+            Some(solver) if child.walls_remaining == [0, 0] => solver.probe(&child),
+            _ => None,
+        }
+    }
+
+    fn inherited_priors(&self, state: &Quorridor, moves: &[Move]) -> Vec<MovePrior> {
+        match &self.inherited {
+            Some(tree) if tree.contains(state) => moves.iter()
+                .map(|mov| {
+                    let mut child = state.clone();
+                    child.make_move(mov);
+                    tree.prior(&child)
+                })
+                .collect(),
+            _ => vec![MovePrior::default(); moves.len()],
+        }
     }
 }
  
@@ -49,15 +240,24 @@ impl Evaluator<MyMCTS> for MyEvaluator {
  
     fn evaluate_new_state(&self, state: &Quorridor, moves: &Vec<Move>,
         _: Option<SearchHandle<MyMCTS>>)
-        -> (Vec<()>, i64) {   
+        -> (Vec<MovePrior>, i64) {   
         // Turn number is available via state.turn_number
         
         // Check for terminal states
         if state.player_pieces[0].y >= (GRID_HEIGHT - 2) as i64 {
-        return (vec![(); moves.len()], 100000);  // Player 0 wins
+        return (vec![MovePrior::default(); moves.len()], 100000);  // Player 0 wins
         }
         if state.player_pieces[1].y <= 1 {
-            return (vec![(); moves.len()], -100000);  // Player 1 wins
+            return (vec![MovePrior::default(); moves.len()], -100000);  // Player 1 wins
+        }
+        
+        // With no walls left the race is looked up exactly. Positions where one side
+        // still has walls are left to the search, since every new wall layout
+        // would need a table of its own.
+        if let Some(solver) = &self.solver
+            && state.walls_remaining == [0, 0]
+            && let Some(result) = solver.probe(state) {
+            return (self.move_priors(state, moves), proven_score(result, state.active_player));
         }
         
         // Use network evaluation if available, otherwise fall back to heuristic
@@ -65,6 +265,10 @@ impl Evaluator<MyMCTS> for MyEvaluator {
             let net = network.lock().unwrap();
             let value = net.evaluate(state);
             (value * 10000.0) as i64  // Scale to match heuristic range
+        } else if let Some(config) = &self.rollout {
+            // Each search thread rolls out with its own thread-local RNG
+            let value = rollout_value(state, config, &mut rand::thread_rng());
+            (value * 10000.0) as i64  // Same scale as the network value
         } else {
             // Use actual BFS shortest path distance to goal
             let p0_distance = shortest_path_to_goal(state, 0);
@@ -78,7 +282,7 @@ impl Evaluator<MyMCTS> for MyEvaluator {
             }
         };
         
-        (vec![(); moves.len()], score)
+        (self.move_priors(state, moves), score)
     }
     
     fn interpret_evaluation_for_player(&self, evaln: &i64, player: &usize) -> i64 {
@@ -95,18 +299,98 @@ impl Evaluator<MyMCTS> for MyEvaluator {
     }
 }
  
-#[derive(Default)]
-pub struct MyMCTS;
+pub struct MyMCTS {
+    node_limit: usize,
+}
+
+impl MyMCTS {
+    // Playouts stop expanding once the tree holds this many nodes
+    pub fn with_node_limit(node_limit: usize) -> Self {
+        MyMCTS { node_limit }
+    }
+}
+
+impl Default for MyMCTS {
+    fn default() -> Self {
+        MyMCTS { node_limit: usize::MAX }
+    }
+}
  
 impl MCTS for MyMCTS {
     type State = Quorridor;
     type Eval = MyEvaluator;
-    type NodeData = ();
+    type NodeData = SolverNode;
     type ExtraThreadData = ();
-    type TreePolicy = UCTPolicy;
-    type TranspositionTable = ApproxTable<Self>;
+    type TreePolicy = PriorUCTPolicy;
+    type TranspositionTable = QuorridorTable;
+
+    fn node_limit(&self) -> usize {
+        self.node_limit
+    }
+
+    fn select_child_after_search<'a>(&self, children: &'a [MoveInfo<Self>]) -> &'a MoveInfo<Self> {
+        // The fastest proven win, else the most visited open move, else the slowest
+        // loss. Inherited visits count towards the choice just like fresh ones;
+        // pruned moves, which include walls that cut off a goal, come last.
+        children.iter().max_by_key(|child| {
+            if child.move_evaluation().pruned {
+                return (-1, 0, 0);
+            }
+            let visits = child.visits() + child.move_evaluation().visits;
+            match child_result(child).map(Proven::for_parent) {
+                Some(Proven::Win(plies)) => (2, -(plies as i64), visits),
+                None => (1, 0, visits),
+                Some(Proven::Loss(plies)) => (0, plies as i64, visits),
+            }
+        }).unwrap()
+    }
+
+    fn on_backpropagation(&self, _: &i64, handle: SearchHandle<Self>) {
+        let node = handle.node();
+        if node.data().get().is_none()
+            && let Some(result) = solve_from_children(node.moves()) {
+            node.data().set(result);
+        }
+    }
 
     fn cycle_behaviour(&self) -> CycleBehaviour<Self> {
-        CycleBehaviour::UseCurrentEvalWhenCycleDetected
+        // Pawns shuffling back and forth repeat positions; a repetition scores as a draw
+        CycleBehaviour::UseThisEvalWhenCycleDetected(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wall::Orientation;
+
+    #[test]
+    fn test_edge_score_counts_inherited_statistics() {
+        let policy = PriorUCTPolicy::new(1.0);
+        let ln_total = (101.0f64).ln();
+        assert_eq!(policy.edge_score(0, 0, ln_total), f64::INFINITY);
+        // Equal visits: the better mean wins
+        assert!(policy.edge_score(50, 50 * 300, ln_total) > policy.edge_score(50, 50 * 100, ln_total));
+        // The same mean with fewer visits gets the larger exploration bonus
+        assert!(policy.edge_score(10, 10 * 100, ln_total) > policy.edge_score(90, 90 * 100, ln_total));
+        assert_eq!(policy.edge_score(4, 400, 1.0), 2.0 * 0.5 + 100.0);
+    }
+
+    #[test]
+    fn test_walls_that_cut_off_a_goal_are_pruned() {
+        let state = crate::quorridor::cornered_position();
+        let moves = state.available_moves();
+        let (priors, _) = MyEvaluator::new().evaluate_new_state(&state, &moves, None);
+        let pruned: Vec<&Move> = moves.iter().zip(&priors).filter(|(_, p)| p.pruned).map(|(m, _)| m).collect();
+        assert!(pruned.contains(&&Move::PlaceWall(0, 4, Orientation::Horizontal)));
+
+        // Exactly the walls the full path check rejects
+        for mov in &moves {
+            let blocks = match mov {
+                Move::PlaceWall(x, y, orientation) => state.wall_blocks_path(*x, *y, *orientation),
+                _ => false,
+            };
+            assert_eq!(pruned.contains(&mov), blocks, "{:?}", mov);
+        }
     }
 }
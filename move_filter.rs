@@ -0,0 +1,86 @@
+use rand::Rng;
+
+use crate::moves::Move;
+use crate::quorridor::{Quorridor, shortest_path_route, GRID_WIDTH, GRID_HEIGHT};
+use crate::wall::Wall;
+
+// Search option that narrows the up-to-128 wall placements at each node down to
+// the ones likely to matter. Pawn moves are never filtered.
+#[derive(Clone, Debug)]
+pub struct WallFilter {
+    pub radius: i64,         // Keep walls within this many squares of either pawn
+    pub sample_rate: f64,    // Chance of keeping each wall that fails both tests
+}
+
+impl Default for WallFilter {
+    fn default() -> Self {
+        WallFilter { radius: 1, sample_rate: 0.05 }
+    }
+}
+
+impl WallFilter {
+    // For each move, whether the search should consider it
+    pub fn keep_mask<R: Rng>(&self, state: &Quorridor, moves: &[Move], rng: &mut R) -> Vec<bool> {
+        // Grid cells a wall would have to cover to cut either player's current shortest path
+        let mut on_path = [[false; GRID_WIDTH]; GRID_HEIGHT];
+        for player in 0..2 {
+            let Some(route) = shortest_path_route(state, player) else { continue };
+            for step in route.windows(2) {
+                let (x, y) = ((step[0].0 + step[1].0) / 2, (step[0].1 + step[1].1) / 2);
+                on_path[y as usize][x as usize] = true;
+            }
+        }
+
+        // Walls live on even lines and pawns on odd ones, so one square is two grid units
+        let reach = 2 * self.radius + 1;
+        let near_pawn = |x: i64, y: i64| state.player_pieces.iter()
+            .any(|p| (x - p.x).abs() <= reach && (y - p.y).abs() <= reach);
+
+        moves.iter()
+            .map(|mov| match mov {
+                Move::PlaceWall(x, y, orientation) => {
+                    let wall = Wall { x: *x, y: *y, orientation: *orientation };
+                    let cells = wall.positions();
+                    cells.iter().any(|&(cx, cy)| on_path[cy as usize][cx as usize])
+                        || near_pawn(cells[1].0, cells[1].1)
+                        || rng.gen_bool(self.sample_rate)
+                }
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcts::GameState;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_filter_keeps_pawn_moves_and_drops_distant_walls() {
+        let game = Quorridor::default();
+        let moves = game.available_moves();
+        let filter = WallFilter { radius: 1, sample_rate: 0.0 };
+        let keep = filter.keep_mask(&game, &moves, &mut StdRng::seed_from_u64(1));
+
+        for (mov, keep) in moves.iter().zip(&keep) {
+            if !matches!(mov, Move::PlaceWall(..)) {
+                assert!(keep, "pawn move {:?} was filtered", mov);
+            }
+        }
+        let kept_walls = moves.iter().zip(&keep).filter(|(m, k)| matches!(m, Move::PlaceWall(..)) && **k).count();
+        assert!(kept_walls > 0);
+        assert!(kept_walls < moves.len() - 4);
+    }
+
+    #[test]
+    fn test_shortest_route_matches_distance() {
+        let game = Quorridor::default();
+        for player in 0..2 {
+            let route = shortest_path_route(&game, player).unwrap();
+            assert_eq!(route.len() - 1, crate::quorridor::shortest_path_to_goal(&game, player).unwrap());
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::moves::Move;
+use crate::quorridor::{Quorridor, move_delta};
+use crate::wall::Orientation;
+use mcts::GameState;
+
+// Move notation shared by every front-end.
+// Pawn moves name the destination square: column letter a-i, row number 1-9
+// (grid x = 2 * column + 1, grid y = 2 * (row - 1) + 1).
+// Walls name their anchor on the even grid lines plus an orientation:
+// column letter for x / 2, digit for y / 2, then 'h' or 'v' ("e3h" is x=8, y=6).
+
+pub fn square_name(x: i64, y: i64) -> String {
+    let column = (b'a' + ((x - 1) / 2) as u8) as char;
+    format!("{}{}", column, (y - 1) / 2 + 1)
+}
+
+pub fn parse_square(text: &str) -> Option<(i64, i64)> {
+    let mut chars = text.chars();
+    let column = chars.next()?;
+    let row: i64 = chars.as_str().parse().ok()?;
+    if !('a'..='i').contains(&column) || !(1..=9).contains(&row) {
+        return None;
+    }
+    Some(((column as i64 - 'a' as i64) * 2 + 1, (row - 1) * 2 + 1))
+}
+
+pub fn wall_name(x: i64, y: i64, orientation: Orientation) -> String {
+    let column = (b'a' + (x / 2) as u8) as char;
+    let suffix = match orientation {
+        Orientation::Horizontal => 'h',
+        Orientation::Vertical => 'v',
+    };
+    format!("{}{}{}", column, y / 2, suffix)
+}
+
+// Notation for `mov` played by the side to move in `state`
+pub fn move_to_notation(state: &Quorridor, mov: &Move) -> String {
+    match mov {
+        Move::PlaceWall(x, y, orientation) => wall_name(*x, *y, *orientation),
+        _ => {
+            let (dx, dy) = move_delta(mov).unwrap();
+            let piece = state.player_pieces[state.active_player];
+            square_name(piece.x + dx, piece.y + dy)
+        }
+    }
+}
+
+// Parse notation into the matching legal move, or None if it is malformed or illegal
+pub fn parse_move(state: &Quorridor, text: &str) -> Option<Move> {
+    let text = text.trim().to_ascii_lowercase();
+    state.available_moves()
+        .into_iter()
+        .find(|mov| move_to_notation(state, mov) == text)
+}
+
+// Notation for a sequence of moves played one after another from `state`
+pub fn line_to_notation(state: &Quorridor, moves: &[Move]) -> Vec<String> {
+    let mut state = state.clone();
+    moves.iter()
+        .map(|mov| {
+            let text = move_to_notation(&state, mov);
+            state.make_move(mov);
+            text
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square_round_trip() {
+        assert_eq!(square_name(1, 1), "a1");
+        assert_eq!(square_name(17, 17), "i9");
+        assert_eq!(parse_square("e5"), Some((9, 9)));
+        assert_eq!(parse_square("j1"), None);
+    }
+
+    #[test]
+    fn test_every_legal_move_round_trips() {
+        let mut state = Quorridor::default();
+        state.make_move(&Move::PlaceWall(8, 4, Orientation::Horizontal));
+        for mov in state.available_moves() {
+            let text = move_to_notation(&state, &mov);
+            assert_eq!(parse_move(&state, &text), Some(mov), "{}", text);
+        }
+    }
+}
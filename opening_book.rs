@@ -0,0 +1,254 @@
+use mcts::GameState;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+use crate::engine::{Engine, position_after};
+use crate::game_record::GameRecord;
+use crate::moves::Move;
+use crate::notation::parse_move;
+use crate::ponder::PonderStats;
+use crate::quorridor::Quorridor;
+use crate::search_result::SearchResult;
+use crate::time_control::SearchLimits;
+
+// Plies played from the book when no depth is given
+pub const DEFAULT_BOOK_DEPTH: usize = 8;
+
+// Moves seen fewer times than this are left to the search
+pub const DEFAULT_MIN_GAMES: u32 = 2;
+
+// Keeps lines that scored badly in the rotation, just rarely
+const MIN_WEIGHT_SCORE: f64 = 0.05;
+
+// Results of one move from a book position, from the mover's side
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BookMove {
+    pub notation: String,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+}
+
+impl BookMove {
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games.max(1) as f64
+    }
+
+    fn weight(&self) -> f64 {
+        self.games as f64 * self.score().max(MIN_WEIGHT_SCORE)
+    }
+}
+
+// Move statistics for the first `max_ply` plies of recorded games, keyed by
+// Zobrist hash so transposed move orders share an entry
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OpeningBook {
+    pub max_ply: usize,
+    pub positions: HashMap<u64, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn new(max_ply: usize) -> Self {
+        OpeningBook { max_ply, positions: HashMap::new() }
+    }
+
+    pub fn from_records(records: &[GameRecord], max_ply: usize) -> Result<Self, String> {
+        let mut book = OpeningBook::new(max_ply);
+        for record in records {
+            book.add_record(record)?;
+        }
+        Ok(book)
+    }
+
+    pub fn add_record(&mut self, record: &GameRecord) -> Result<(), String> {
+        let mut state = Quorridor::default();
+        for (text, mov) in record.moves.iter().zip(record.parse_moves()?).take(self.max_ply) {
+            let mover = state.active_player;
+            let entries = self.positions.entry(state.zobrist).or_default();
+            let index = match entries.iter().position(|entry| &entry.notation == text) {
+                Some(index) => index,
+                None => {
+                    entries.push(BookMove { notation: text.clone(), games: 0, wins: 0, draws: 0 });
+                    entries.len() - 1
+                }
+            };
+            let entry = &mut entries[index];
+            entry.games += 1;
+            match record.winner {
+                Some(w) if w == mover => entry.wins += 1,
+                None => entry.draws += 1,
+                Some(_) => {}
+            }
+            state.make_move(&mov);
+        }
+        Ok(())
+    }
+
+    // Book moves for `state` with at least `min_games` games, most played first.
+    // A hash collision shows up as a move that is not legal here and is skipped.
+    pub fn lookup(&self, state: &Quorridor, min_games: u32) -> Vec<(Move, &BookMove)> {
+        let Some(entries) = self.positions.get(&state.zobrist) else {
+            return Vec::new();
+        };
+        let legal = state.legal_moves();
+        let mut moves: Vec<(Move, &BookMove)> = entries.iter()
+            .filter(|entry| entry.games >= min_games)
+            .filter_map(|entry| parse_move(state, &entry.notation).filter(|mov| legal.contains(mov)).map(|mov| (mov, entry)))
+            .collect();
+        moves.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.games));
+        moves
+    }
+
+    // A book move picked at random, weighted by games played times score
+    pub fn pick<R: Rng>(&self, state: &Quorridor, min_games: u32, rng: &mut R) -> Option<Move> {
+        self.lookup(state, min_games)
+            .choose_weighted(rng, |(_, entry)| entry.weight())
+            .ok()
+            .map(|(mov, _)| mov.clone())
+    }
+
+    // A random line of book moves from the start position, up to `depth` plies
+    // or until the book runs out
+    pub fn sample_line<R: Rng>(&self, depth: usize, min_games: u32, rng: &mut R) -> Vec<Move> {
+        let mut state = Quorridor::default();
+        let mut line = Vec::new();
+        while line.len() < depth.min(self.max_ply) && !state.game_over() {
+            let Some(mov) = self.pick(&state, min_games, rng) else { break };
+            state.make_move(&mov);
+            line.push(mov);
+        }
+        line
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::to_writer(BufWriter::new(file), self).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+// Plays book moves for the first `depth` plies of a game from the standard
+// start, then hands over to the wrapped engine
+pub struct BookEngine {
+    inner: Box<dyn Engine>,
+    book: Arc<OpeningBook>,
+    depth: usize,
+    min_games: u32,
+    position: Option<Quorridor>,  // None when the game did not start from the standard position
+    ply: usize,
+    rng: rand::rngs::ThreadRng,
+}
+
+impl BookEngine {
+    pub fn new(inner: Box<dyn Engine>, book: Arc<OpeningBook>, depth: usize) -> Self {
+        BookEngine { inner, book, depth, min_games: DEFAULT_MIN_GAMES, position: None, ply: 0, rng: rand::thread_rng() }
+    }
+}
+
+impl Engine for BookEngine {
+    fn name(&self) -> String {
+        format!("{}+book", self.inner.name())
+    }
+
+    fn new_game(&mut self) {
+        self.inner.new_game();
+    }
+
+    fn set_position(&mut self, start: &Quorridor, moves: &[Move]) {
+        self.inner.set_position(start, moves);
+        self.position = (start.zobrist == Quorridor::default().zobrist).then(|| position_after(start, moves));
+        self.ply = moves.len();
+    }
+
+    fn search(&mut self, limits: &SearchLimits) -> SearchResult {
+        let started = Instant::now();
+        if let Some(position) = &self.position
+            && self.ply < self.depth
+            && let Some(mov) = self.book.pick(position, self.min_games, &mut self.rng) {
+            return SearchResult::without_search(position, Some(mov), started.elapsed());
+        }
+        self.inner.search(limits)
+    }
+
+    fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.inner.stop_flag()
+    }
+
+    fn start_ponder(&mut self) {
+        self.inner.start_ponder();
+    }
+
+    fn stop_ponder(&mut self) -> Option<PonderStats> {
+        self.inner.stop_ponder()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::{BaselineBot, BaselineKind};
+    use crate::game_record::{Termination, test_record};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn record(moves: &[&str], winner: Option<usize>) -> GameRecord {
+        GameRecord { winner, termination: Termination::Goal, ..test_record(moves) }
+    }
+
+    #[test]
+    fn test_book_counts_results_from_the_movers_side() {
+        let records = [
+            record(&["e2", "e8", "e3"], Some(0)),
+            record(&["e2", "e8", "d2"], Some(1)),
+            record(&["d1", "e8"], None),
+        ];
+        let book = OpeningBook::from_records(&records, 2).unwrap();
+        let start = Quorridor::default();
+        let moves = book.lookup(&start, 1);
+        assert_eq!(moves[0].1, &BookMove { notation: "e2".to_string(), games: 2, wins: 1, draws: 0 });
+        assert_eq!(moves[1].1.draws, 1);
+        // The third ply is past max_ply
+        assert_eq!(book.positions.len(), 3);
+
+        assert!(book.lookup(&start, 3).is_empty());
+        let line = book.sample_line(10, 1, &mut StdRng::seed_from_u64(1));
+        assert_eq!(line.len(), 2);
+    }
+
+    #[test]
+    fn test_book_engine_hands_over_after_depth() {
+        // Sideways moves the shortest-path bot would never play
+        let records = [record(&["d1", "d9"], None), record(&["d1", "d9"], None)];
+        let book = Arc::new(OpeningBook::from_records(&records, 2).unwrap());
+        let bot = || Box::new(BaselineBot::new(BaselineKind::ShortestPath));
+        let start = Quorridor::default();
+
+        let mut engine = BookEngine::new(bot(), book.clone(), 2);
+        engine.set_position(&start, &[]);
+        assert_eq!(engine.search(&SearchLimits::default()).best_move, Some(Move::Left));
+        engine.set_position(&start, &[Move::Left]);
+        assert_eq!(engine.search(&SearchLimits::default()).best_move, Some(Move::Left));
+
+        // Past the depth the inner engine plays, even though the book knows the position
+        let mut engine = BookEngine::new(bot(), book.clone(), 1);
+        engine.set_position(&start, &[Move::Left]);
+        assert_eq!(engine.search(&SearchLimits::default()).best_move, Some(Move::Up));
+
+        // The book only covers games from the standard start
+        let mut other_start = Quorridor { walls_remaining: [9, 10], ..Default::default() };
+        other_start.recompute_hash();
+        engine.set_position(&other_start, &[]);
+        assert_eq!(engine.search(&SearchLimits::default()).best_move, Some(Move::Down));
+    }
+}
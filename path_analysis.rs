@@ -0,0 +1,160 @@
+// What the path-distance heuristic sees, for display: each player's shortest
+// route, the edges every shortest route has to cross, and how much each
+// candidate wall would lengthen either player's route.
+use mcts::GameState;
+use std::collections::VecDeque;
+
+use crate::moves::Move;
+use crate::notation::move_to_notation;
+use crate::quorridor::{Quorridor, GRID_HEIGHT, GRID_WIDTH, UNREACHABLE, distance_to_goal_map, shortest_path_route, shortest_path_to_goal};
+
+const STEPS: [(i64, i64); 4] = [(2, 0), (-2, 0), (0, 2), (0, -2)];
+
+// Grid cells are addressed as (x, y); an edge between two squares by the cell between them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathOverlay {
+    pub routes: [Vec<(i64, i64)>; 2],    // One shortest route per player, pawn square first
+    pub critical: [Vec<(i64, i64)>; 2],  // Edges on every shortest route per player
+}
+
+pub fn path_overlay(game: &Quorridor) -> PathOverlay {
+    PathOverlay {
+        routes: [0, 1].map(|player| shortest_path_route(game, player).unwrap_or_default()),
+        critical: [0, 1].map(|player| critical_edges(game, player)),
+    }
+}
+
+fn on_board(x: i64, y: i64) -> bool {
+    x >= 1 && x <= (GRID_WIDTH - 2) as i64 && y >= 1 && y <= (GRID_HEIGHT - 2) as i64
+}
+
+// Steps from the player's pawn to every square, pawns ignored as in the heuristic
+fn distance_from_pawn(game: &Quorridor, player: usize) -> [[usize; GRID_WIDTH]; GRID_HEIGHT] {
+    let start = game.player_pieces[player];
+    let mut distances = [[UNREACHABLE; GRID_WIDTH]; GRID_HEIGHT];
+    distances[start.y as usize][start.x as usize] = 0;
+    let mut queue = VecDeque::from([(start.x, start.y)]);
+    while let Some((x, y)) = queue.pop_front() {
+        let d = distances[y as usize][x as usize];
+        for (dx, dy) in STEPS {
+            let (nx, ny) = (x + dx, y + dy);
+            if on_board(nx, ny) && distances[ny as usize][nx as usize] == UNREACHABLE && !game.wall_collision((x + nx) / 2, (y + ny) / 2) {
+                distances[ny as usize][nx as usize] = d + 1;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    distances
+}
+
+// Edges that lie on every shortest route of `player`: a wall across any of
+// them makes the route longer. A step u -> v is on some shortest route when
+// from_pawn(u) + 1 + to_goal(v) equals the route length, and on all of them
+// when it is the only such step leaving its distance layer.
+pub fn critical_edges(game: &Quorridor, player: usize) -> Vec<(i64, i64)> {
+    let to_goal = distance_to_goal_map(game, player);
+    let from_pawn = distance_from_pawn(game, player);
+    let start = game.player_pieces[player];
+    let length = to_goal[start.y as usize][start.x as usize];
+    if length == UNREACHABLE || length == 0 {
+        return Vec::new();
+    }
+
+    let mut layers: Vec<Vec<(i64, i64)>> = vec![Vec::new(); length];
+    for y in (1..GRID_HEIGHT as i64 - 1).step_by(2) {
+        for x in (1..GRID_WIDTH as i64 - 1).step_by(2) {
+            let d = from_pawn[y as usize][x as usize];
+            if d >= length {
+                continue;
+            }
+            for (dx, dy) in STEPS {
+                let (nx, ny) = (x + dx, y + dy);
+                let (ex, ey) = ((x + nx) / 2, (y + ny) / 2);
+                if on_board(nx, ny) && !game.wall_collision(ex, ey) && to_goal[ny as usize][nx as usize] == length - d - 1 {
+                    layers[d].push((ex, ey));
+                }
+            }
+        }
+    }
+    layers.into_iter().filter(|edges| edges.len() == 1).map(|edges| edges[0]).collect()
+}
+
+// A legal wall for the side to move and what it does to both routes
+#[derive(Clone, Debug, PartialEq)]
+pub struct WallImpact {
+    pub mov: Move,
+    pub notation: String,
+    pub delta: [i64; 2],  // Change in each player's shortest-path length
+}
+
+impl WallImpact {
+    // How much the wall helps `player`: the opponent's loss minus their own
+    pub fn gain_for(&self, player: usize) -> i64 {
+        self.delta[1 - player] - self.delta[player]
+    }
+}
+
+// Walls the side to move could place that change either distance, best for
+// the side to move first
+pub fn wall_impacts(game: &Quorridor) -> Vec<WallImpact> {
+    let before = [0, 1].map(|player| shortest_path_to_goal(game, player).map_or(0, |d| d as i64));
+    let mover = game.active_player;
+    let mut impacts: Vec<WallImpact> = game.legal_moves().into_iter()
+        .filter(|mov| matches!(mov, Move::PlaceWall(..)))
+        .filter_map(|mov| {
+            let mut after = game.clone();
+            after.make_move(&mov);
+            let delta = [0, 1].map(|player| shortest_path_to_goal(&after, player).map_or(0, |d| d as i64) - before[player]);
+            (delta != [0, 0]).then(|| WallImpact { notation: move_to_notation(game, &mov), mov, delta })
+        })
+        .collect();
+    impacts.sort_by_key(|impact| (-impact.gain_for(mover), impact.notation.clone()));
+    impacts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wall::Orientation;
+
+    #[test]
+    fn test_open_board_route_is_straight_and_critical() {
+        let game = Quorridor::default();
+        let overlay = path_overlay(&game);
+        assert_eq!(overlay.routes[0].len(), 9);
+        // Any sidestep costs a move, so every edge of the straight route is critical
+        assert_eq!(overlay.critical[0], (2..17).step_by(2).map(|y| (9, y)).collect::<Vec<_>>());
+
+        let impacts = wall_impacts(&game);
+        assert!(impacts.iter().all(|impact| impact.delta.iter().all(|&d| d >= 0)));
+        // Both pawns share column e, so a wall across it costs each of them a step
+        assert!(impacts.iter().any(|impact| impact.notation == "e4h" && impact.delta == [1, 1]));
+    }
+
+    #[test]
+    fn test_corridor_edge_is_critical() {
+        let mut game = Quorridor::default();
+        // Vertical walls either side of column e from row 1 to row 4 leave a corridor
+        // whose only exit is straight up
+        for y in [0, 4] {
+            for x in [8, 10] {
+                game.walls_remaining = [10, 10];
+                game.make_move(&Move::PlaceWall(x, y, Orientation::Vertical));
+            }
+        }
+        let critical = critical_edges(&game, 0);
+        assert!(critical.contains(&(9, 2)));
+        assert!(critical.contains(&(9, 6)));
+
+        // Closing the corridor's mouth is the wall that hurts player 0 most
+        game.active_player = 1;
+        let impacts = wall_impacts(&game);
+        let best = &impacts[0];
+        assert!(best.delta[0] > 0);
+        assert!(critical.iter().any(|&(x, y)| match best.mov {
+            Move::PlaceWall(wx, wy, Orientation::Horizontal) => y == wy && (x == wx + 1 || x == wx + 3),
+            Move::PlaceWall(wx, wy, Orientation::Vertical) => x == wx && (y == wy + 1 || y == wy + 3),
+            _ => false,
+        }));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::quorridor::Quorridor;
+use crate::zobrist::pawn_key;
 
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Piece {
@@ -9,6 +10,8 @@ pub struct Piece {
 pub fn move_player(game: &mut Quorridor, dx: i64, dy: i64) {
     let idx = game.active_player;
     // In 18x18 grid, movements are by 2 (odd to odd)
+    game.zobrist ^= pawn_key(idx, game.player_pieces[idx].x, game.player_pieces[idx].y);
     game.player_pieces[idx].x = game.player_pieces[idx].x + dx;
     game.player_pieces[idx].y = game.player_pieces[idx].y + dy;
+    game.zobrist ^= pawn_key(idx, game.player_pieces[idx].x, game.player_pieces[idx].y);
 }
@@ -0,0 +1,56 @@
+use mcts::*;
+use std::time::{Duration, Instant};
+
+use crate::mcts_impl::{MyMCTS, MyEvaluator, PriorUCTPolicy};
+use crate::quorridor::Quorridor;
+use crate::tree_reuse::TreeReuse;
+use crate::transposition::{QuorridorTable, ReplacementPolicy};
+
+// Pondering stops growing the tree past this many nodes to bound memory
+pub const PONDER_NODE_LIMIT: usize = 200_000;
+
+// Background search on the opponent's time. Threads keep expanding the tree for
+// the position the opponent is thinking about until `stop` is called, the node
+// limit is reached, or the Ponder is dropped.
+pub struct Ponder {
+    search: AsyncSearchOwned<MyMCTS>,
+    started: Instant,
+}
+
+impl Ponder {
+    pub fn start(state: &Quorridor, evaluator: &MyEvaluator, reuse: &TreeReuse, exploration: f64,
+                 threads: usize, table_mb: usize) -> Self {
+        Ponder::start_with_node_limit(state, evaluator, reuse, exploration, threads, table_mb, PONDER_NODE_LIMIT)
+    }
+
+    pub fn start_with_node_limit(state: &Quorridor, evaluator: &MyEvaluator, reuse: &TreeReuse,
+                                 exploration: f64, threads: usize, table_mb: usize, node_limit: usize) -> Self {
+        let mcts = MCTSManager::new(
+            state.clone(),
+            MyMCTS::with_node_limit(node_limit),
+            evaluator.with_inherited(reuse.inherited()),
+            PriorUCTPolicy::new(exploration),
+            QuorridorTable::for_nodes(node_limit as u64, table_mb, ReplacementPolicy::KeepOldest)
+        );
+        Ponder {
+            search: mcts.into_playout_parallel_async(threads.max(1)),
+            started: Instant::now(),
+        }
+    }
+
+    // Halt the search threads and hand back the tree built so far
+    pub fn stop(self) -> (MCTSManager<MyMCTS>, PonderStats) {
+        let elapsed = self.started.elapsed();
+        let mcts = self.search.halt();
+        let playouts = mcts.tree().root_node().moves().map(|m| m.visits()).sum();
+        let nodes = mcts.tree().num_nodes();
+        (mcts, PonderStats { playouts, nodes, elapsed })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PonderStats {
+    pub playouts: u64,
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
@@ -0,0 +1,434 @@
+// Line-based engine protocol modeled on UCI, for GUIs, tournament managers and
+// scripts. Commands, one per line:
+//   uqi                                   identify, list options, answer uqiok
+//   isready                               answer readyok
+//   setoption name <name> value <value>   see OPTIONS
+//   newgame                               forget the previous game
+//   position startpos [moves <m1> <m2> ...]
+//   go [playouts N] [movetime MS] [depth N] [p0time MS] [p1time MS] [p0inc MS] [p1inc MS] [infinite]
+//   stop                                  end the running search early
+//   quit
+// `go infinite` runs until stop, or until an MCTS tree holds as many nodes as
+// its Hash table was sized for.
+// A search answers with an `info` line and then `bestmove <move> [ponder <move>]`,
+// moves written in the notation of the notation module.
+use mcts::GameState;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc;
+use std::thread;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::alphabeta::MAX_DEPTH;
+use crate::engine::{Engine, ENGINE_NAMES};
+use crate::engine_config::{EngineConfig, check_exploration};
+use crate::moves::Move;
+use crate::notation::parse_move;
+use crate::quorridor::Quorridor;
+use crate::search_result::SearchResult;
+use crate::time_control::{ClockState, SearchLimits};
+
+pub const ENGINE_ID: &str = "Quorridor";
+
+// Options accepted by setoption, listed by uqi
+const OPTIONS: [(&str, &str); 7] = [
+    ("Engine", "type string default mcts"),
+    ("Threads", "type spin default 1 min 1 max 64"),
+    ("Hash", "type spin default 64 min 1 max 65536"),
+    ("Exploration", "type string default 0.5"),
+    ("Network", "type string default model.safetensors"),
+    ("Book", "type string default <empty>"),
+    ("BookDepth", "type spin default 8 min 0 max 64"),
+];
+
+// Sets the stop flag of whatever engine the session is running, from another
+// thread, e.g. the one reading stdin while a search runs. That thread clears
+// the flag when it reads go and sets it on stop, so both happen in input order
+// even when the session has not reached the go yet.
+#[derive(Clone, Default)]
+pub struct StopHandle(Arc<Mutex<StopState>>);
+
+#[derive(Default)]
+struct StopState {
+    flag: Option<Arc<AtomicBool>>,
+    stopped: bool,  // Passed on to an engine installed after the stop
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.stopped = true;
+        if let Some(flag) = &state.flag {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.stopped = false;
+        if let Some(flag) = &state.flag {
+            flag.store(false, Ordering::Relaxed);
+        }
+    }
+
+    fn set(&self, flag: Option<Arc<AtomicBool>>) {
+        let mut state = self.0.lock().unwrap();
+        if let Some(flag) = &flag {
+            flag.store(state.stopped, Ordering::Relaxed);
+        }
+        state.flag = flag;
+    }
+}
+
+pub struct Session<W: Write> {
+    out: W,
+    config: EngineConfig,
+    engine: Option<Box<dyn Engine>>,  // Rebuilt on first use after an option changes
+    start: Quorridor,
+    moves: Vec<Move>,
+    stop: StopHandle,
+}
+
+impl<W: Write> Session<W> {
+    pub fn new(out: W) -> Self {
+        Session {
+            out,
+            config: EngineConfig::parse("mcts").unwrap(),
+            engine: None,
+            start: Quorridor::default(),
+            moves: Vec::new(),
+            stop: StopHandle::default(),
+        }
+    }
+
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    pub fn into_output(self) -> W {
+        self.out
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.out, "{}", line).expect("protocol output closed");
+        self.out.flush().expect("protocol output closed");
+    }
+
+    // Handle one command line; false once the session should end
+    pub fn handle_line(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+        match command {
+            "uqi" => self.identify(),
+            "isready" => self.send("readyok"),
+            "setoption" => self.set_option(args),
+            "newgame" => {
+                if let Some(engine) = self.engine.as_mut() {
+                    engine.new_game();
+                }
+                self.start = Quorridor::default();
+                self.moves.clear();
+            }
+            "position" => self.set_position(args),
+            "go" => self.go(args),
+            // A search has already returned by the time its stop line is read here
+            "stop" => {}
+            "quit" => return false,
+            _ => self.send(&format!("info string unknown command '{}'", command)),
+        }
+        true
+    }
+
+    fn identify(&mut self) {
+        self.send(&format!("id name {} {}", ENGINE_ID, env!("CARGO_PKG_VERSION")));
+        for (name, spec) in OPTIONS {
+            self.send(&format!("option name {} {}", name, spec));
+        }
+        self.send(&format!("info string engines: {}", ENGINE_NAMES.join(", ")));
+        self.send("uqiok");
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        let value_at = args.iter().position(|&a| a == "value");
+        let (Some(&"name"), Some(value_at)) = (args.first(), value_at) else {
+            self.send("info string expected setoption name <name> value <value>");
+            return;
+        };
+        let name = args[1..value_at].join(" ");
+        let value = args[value_at + 1..].join(" ");
+
+        let mut config = self.config.clone();
+        let applied: Result<(), String> = match name.to_ascii_lowercase().as_str() {
+            // An engine spec such as "alphabeta:depth=4", replacing every option set so far
+            "engine" => EngineConfig::parse(&value).map(|parsed| config = parsed),
+            "threads" => value.parse().map(|n: usize| config.threads = n.max(1)).map_err(|e| e.to_string()),
+            "hash" => value.parse().map(|mb| config.table_mb = mb).map_err(|e| e.to_string()),
+            "exploration" => value.parse::<f64>().map_err(|e| e.to_string())
+                .and_then(check_exploration)
+                .map(|c| config.exploration = c),
+            "network" => {
+                config.network = Some(value.clone());
+                Ok(())
+            }
+            "book" => {
+                config.book = (!value.is_empty() && value != "<empty>").then(|| value.clone());
+                Ok(())
+            }
+            "bookdepth" => value.parse().map(|d| config.book_depth = d).map_err(|e| e.to_string()),
+            _ => Err("unknown option".to_string()),
+        };
+        // Build now so a bad value is reported here rather than at the next go
+        match applied.and_then(|()| config.build()) {
+            Ok(engine) => {
+                self.config = config;
+                self.install(engine);
+            }
+            Err(e) => self.send(&format!("info string setoption {}: {}", name, e)),
+        }
+    }
+
+    fn install(&mut self, mut engine: Box<dyn Engine>) {
+        engine.set_position(&self.start, &self.moves);
+        self.stop.set(Some(engine.stop_flag()));
+        self.engine = Some(engine);
+    }
+
+    fn set_position(&mut self, args: &[&str]) {
+        if args.first() != Some(&"startpos") {
+            self.send("info string only 'position startpos [moves ...]' is supported");
+            return;
+        }
+        let start = Quorridor::default();
+        let mut state = start.clone();
+        let mut moves = Vec::new();
+        let listed = match args.get(1) {
+            Some(&"moves") => &args[2..],
+            Some(other) => {
+                self.send(&format!("info string expected 'moves', got '{}'", other));
+                return;
+            }
+            None => &[],
+        };
+        for (ply, text) in listed.iter().enumerate() {
+            let Some(mov) = parse_move(&state, text).filter(|mov| state.legal_moves().contains(mov)) else {
+                self.send(&format!("info string illegal move '{}' at ply {}", text, ply + 1));
+                return;
+            };
+            state.make_move(&mov);
+            moves.push(mov);
+        }
+        self.start = start;
+        self.moves = moves;
+        if let Some(engine) = self.engine.as_mut() {
+            engine.set_position(&self.start, &self.moves);
+        }
+    }
+
+    fn limits(&self, args: &[&str]) -> Result<SearchLimits, String> {
+        let mut limits = SearchLimits::default();
+        let mut times = [None; 2];
+        let mut increments = [Duration::ZERO; 2];
+        let mut infinite = false;
+        let mut i = 0;
+        while i < args.len() {
+            let value = || -> Result<u64, String> {
+                args.get(i + 1)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| format!("'{}' needs a number", args[i]))
+            };
+            match args[i] {
+                "infinite" => {
+                    infinite = true;
+                    i += 1;
+                    continue;
+                }
+                "playouts" => limits.playouts = Some(value()?),
+                "movetime" => limits.move_time = Some(Duration::from_millis(value()?)),
+                "depth" => limits.depth = Some(value()? as u32),
+                "p0time" => times[0] = Some(Duration::from_millis(value()?)),
+                "p1time" => times[1] = Some(Duration::from_millis(value()?)),
+                "p0inc" => increments[0] = Duration::from_millis(value()?),
+                "p1inc" => increments[1] = Duration::from_millis(value()?),
+                other => return Err(format!("unknown go argument '{}'", other)),
+            }
+            i += 2;
+        }
+
+        let player = self.moves.len() % 2;
+        limits.clock = times[player].map(|remaining| ClockState { remaining, increment: increments[player] });
+        if infinite {
+            // Runs until stop
+            limits = SearchLimits { playouts: Some(u64::MAX), depth: Some(MAX_DEPTH), ..Default::default() };
+        } else if limits.playouts.is_none() && limits.move_time.is_none() && limits.clock.is_none() && limits.depth.is_none() {
+            limits = self.config.limits();
+        }
+        Ok(limits)
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        let limits = match self.limits(args) {
+            Ok(limits) => limits,
+            Err(e) => {
+                self.send(&format!("info string {}", e));
+                return;
+            }
+        };
+        if self.engine.is_none() {
+            match self.config.build() {
+                Ok(engine) => self.install(engine),
+                Err(e) => {
+                    self.send(&format!("info string {}", e));
+                    self.send("bestmove none");
+                    return;
+                }
+            }
+        }
+        let result = self.engine.as_mut().unwrap().search(&limits);
+
+        self.send(&info_line(&result));
+        let answer = match (&result.best_move_notation, result.principal_variation_notation.get(1)) {
+            (Some(best), Some(ponder)) => format!("bestmove {} ponder {}", best, ponder),
+            (Some(best), None) => format!("bestmove {}", best),
+            (None, _) => "bestmove none".to_string(),
+        };
+        self.send(&answer);
+    }
+}
+
+// Run a session over `input`, read on its own thread so that stop and quit
+// reach a running search straight away. Returns once input ends or quit is read.
+pub fn serve<W: Write>(mut session: Session<W>, input: impl BufRead + Send + 'static) -> Session<W> {
+    let stop = session.stop_handle();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in input.lines() {
+            let Ok(line) = line else { break };
+            match line.split_whitespace().next() {
+                Some("go") => stop.clear(),
+                Some("stop") | Some("quit") => stop.stop(),
+                _ => {}
+            }
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    // A closed input ends the session like quit
+    for line in receiver {
+        if !session.handle_line(&line) {
+            break;
+        }
+    }
+    session
+}
+
+// Summary of a finished search: depth, nodes searched and nodes per second for
+// alpha-beta, or playouts, tree size and playouts per second for MCTS; then the
+// mover's win probability, a proven result if any, and the principal variation
+pub fn info_line(result: &SearchResult) -> String {
+    let mut info = match result.depth {
+        Some(depth) => format!("info depth {} nodes {} nps {:.0}", depth, result.playouts, result.playouts_per_second),
+        None => format!("info playouts {} nodes {} pps {:.0}", result.playouts, result.tree_size, result.playouts_per_second),
+    };
+    info += &format!(" time {:.0} wp {:.3}", result.elapsed_ms, result.win_probability);
+    if let Some(proven) = result.proven {
+        info += &format!(" proven {}", proven);
+    }
+    if !result.principal_variation_notation.is_empty() {
+        info += &format!(" pv {}", result.principal_variation_notation.join(" "));
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(lines: &[&str]) -> Vec<String> {
+        let mut session = Session::new(Vec::new());
+        for line in lines {
+            if !session.handle_line(line) {
+                break;
+            }
+        }
+        String::from_utf8(session.into_output()).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_handshake_and_search() {
+        let output = run(&[
+            "uqi",
+            "isready",
+            "setoption name Engine value shortest",
+            "position startpos moves e2 e8",
+            "go",
+            "quit",
+            "go",
+        ]);
+        assert!(output.contains(&"uqiok".to_string()));
+        assert!(output.contains(&"readyok".to_string()));
+        assert!(output.iter().any(|l| l.starts_with("info playouts")));
+        // Only one search ran; the second go came after quit
+        assert_eq!(output.iter().filter(|l| l.starts_with("bestmove")).collect::<Vec<_>>(), ["bestmove e3"]);
+    }
+
+    #[test]
+    fn test_bad_input_is_reported_and_ignored() {
+        let output = run(&[
+            "setoption name Engine value shortest",
+            "position startpos moves e2 e7",
+            "setoption name Threads value many",
+            "setoption name Exploration value nan",
+            "go playouts",
+            "go",
+        ]);
+        assert!(output.iter().any(|l| l == "info string illegal move 'e7' at ply 2"));
+        assert!(output.iter().any(|l| l.starts_with("info string setoption Threads")));
+        assert!(output.iter().any(|l| l.starts_with("info string setoption Exploration")));
+        assert!(output.iter().any(|l| l == "info string 'playouts' needs a number"));
+        // The rejected position left the start position in place
+        assert_eq!(output.last().unwrap(), "bestmove e2");
+    }
+
+    #[test]
+    fn test_stop_ends_go_infinite() {
+        let mut session = Session::new(Vec::new());
+        session.handle_line("setoption name Engine value alphabeta");
+        // Left over from before the search and cleared when go was read
+        session.stop_handle().stop();
+        session.stop_handle().clear();
+        session.handle_line("go depth 2");
+
+        let stop = session.stop_handle();
+        let stopper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            stop.stop();
+        });
+        session.handle_line("go infinite");
+        session.handle_line("stop");
+        stopper.join().unwrap();
+
+        let output: Vec<String> = String::from_utf8(session.into_output()).unwrap().lines().map(String::from).collect();
+        assert!(output[0].starts_with("info depth 2 "), "{}", output[0]);
+        assert_eq!(output.iter().filter(|l| l.starts_with("bestmove e2")).count(), 2);
+    }
+
+    #[test]
+    fn test_stop_right_after_go_infinite() {
+        // The reader runs ahead of the search: stop is read before the session
+        // handles go, and even before it builds the engine
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let input = std::io::Cursor::new("setoption name Engine value alphabeta\ngo infinite\nstop\nquit\n");
+            let session = serve(Session::new(Vec::new()), input);
+            sender.send(session.into_output()).unwrap();
+        });
+        let output = receiver.recv_timeout(Duration::from_secs(30)).expect("go infinite was never stopped");
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().filter(|l| l.starts_with("bestmove")).count(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::piece::Piece;
 use crate::wall::{Wall, Orientation};
+use crate::zobrist;
 use itertools::iproduct;
 use mcts::GameState;
 
@@ -21,6 +22,7 @@ pub struct Quorridor {
     pub active_player: usize,
     pub grid: [[bool; GRID_WIDTH]; GRID_HEIGHT],  // true = wall present
     pub walls_remaining: [usize; 2],
+    pub zobrist: u64,  // Kept up to date by make_move; call recompute_hash after editing fields directly
 }
 
 impl GameState for Quorridor {
@@ -45,29 +47,19 @@ impl GameState for Quorridor {
 
     fn make_move(&mut self, mov: &Self::Move) {
         let success = match mov {
-            crate::Move::Up => { move_player(self, 0, -2); true }
-            crate::Move::Down => { move_player(self, 0, 2); true }
-            crate::Move::Left => { move_player(self, -2, 0); true }
-            crate::Move::Right => { move_player(self, 2, 0); true }
-            crate::Move::UpJump => { move_player(self, 0, -4); true }
-            crate::Move::DownJump => { move_player(self, 0, 4); true }
-            crate::Move::LeftJump => { move_player(self, -4, 0); true }
-            crate::Move::RightJump => { move_player(self, 4, 0); true }
-            crate::Move::UpLeft => { move_player(self, -2, -2); true }
-            crate::Move::UpRight => { move_player(self, 2, -2); true }
-            crate::Move::DownLeft => { move_player(self, -2, 2); true }
-            crate::Move::DownRight => { move_player(self, 2, 2); true }
-            crate::Move::LeftUp => { move_player(self, -2, -2); true }
-            crate::Move::LeftDown => { move_player(self, -2, 2); true }
-            crate::Move::RightUp => { move_player(self, 2, -2); true }
-            crate::Move::RightDown => { move_player(self, 2, 2); true }
             crate::Move::PlaceWall(x, y, orientation) => {
                 place_wall(self, *x, *y, *orientation) == WallPlacementResult::Success
             }
+            _ => {
+                let (dx, dy) = move_delta(mov).unwrap();
+                move_player(self, dx, dy);
+                true
+            }
         };
         
         if success {
             self.active_player = 1 - self.active_player;
+            self.zobrist ^= zobrist::side_to_move_key();
         }
     }
 }
@@ -75,6 +67,10 @@ impl GameState for Quorridor {
 
 impl Quorridor {
 
+    pub fn recompute_hash(&mut self) {
+        self.zobrist = zobrist::full_hash(self);
+    }
+
     pub fn wall_collision(&self, _target_x: i64, _target_y: i64) -> bool {
         if _target_x < 0 || _target_x >= GRID_WIDTH as i64 || _target_y < 0 || _target_y >= GRID_HEIGHT as i64 {
             return true;
@@ -184,6 +180,7 @@ impl Quorridor {
             active_player: self.active_player,
             grid: temp_grid,
             walls_remaining: self.walls_remaining,
+            zobrist: self.zobrist,  // Stale, but only used for the path check
         };
         
         // Check if both players still have a path to their goals
@@ -236,15 +233,75 @@ impl Quorridor {
         moves
     }
 
+    // available_moves without the walls that would cut a player off from their goal
+    pub fn legal_moves(&self) -> Vec<crate::Move> {
+        let moves = self.available_moves();
+        let blocking = self.path_blocking_walls(&moves);
+        moves.into_iter().zip(blocking).filter(|(_, blocks)| !blocks).map(|(mov, _)| mov).collect()
+    }
+
+    // For each move, whether it is a wall that cuts a player off from their goal.
+    // Such a wall must cross each shortest route it cuts, so only walls on one
+    // of the two current routes need the full path check.
+    pub fn path_blocking_walls(&self, moves: &[crate::Move]) -> Vec<bool> {
+        let mut on_route = [[false; GRID_WIDTH]; GRID_HEIGHT];
+        for player in 0..2 {
+            match shortest_path_route(self, player) {
+                Some(route) => for step in route.windows(2) {
+                    on_route[((step[0].1 + step[1].1) / 2) as usize][((step[0].0 + step[1].0) / 2) as usize] = true;
+                },
+                // Already cut off; let the full check decide every wall
+                None => on_route = [[true; GRID_WIDTH]; GRID_HEIGHT],
+            }
+        }
+        moves.iter()
+            .map(|mov| match mov {
+                crate::Move::PlaceWall(x, y, orientation) => {
+                    let wall = Wall { x: *x, y: *y, orientation: *orientation };
+                    wall.positions().iter().any(|&(cx, cy)| on_route[cy as usize][cx as usize])
+                        && self.wall_blocks_path(*x, *y, *orientation)
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
     pub fn game_over(&self) -> bool {
         self.player_pieces[0].y >= (GRID_HEIGHT - 2) as i64 || self.player_pieces[1].y <= 1
     }
+
+    pub fn winner(&self) -> Option<usize> {
+        if self.player_pieces[0].y >= (GRID_HEIGHT - 2) as i64 {
+            Some(0)
+        } else if self.player_pieces[1].y <= 1 {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
+
+// Player 0 in the a1 corner with a wall on their right, so the only way out is
+// down from a2; player 1 to move. A horizontal wall at (0, 4) would shut them in.
+#[cfg(test)]
+pub(crate) fn cornered_position() -> Quorridor {
+    let mut game = Quorridor {
+        player_pieces: [Piece { x: 1, y: 1 }, Piece { x: 17, y: 9 }],
+        active_player: 1,
+        ..Default::default()
+    };
+    for (x, y) in (Wall { x: 2, y: 0, orientation: Orientation::Vertical }).positions() {
+        game.grid[y as usize][x as usize] = true;
+    }
+    game.walls_remaining[0] -= 1;
+    game.recompute_hash();
+    game
 }
 
 impl Default for Quorridor {
     fn default() -> Self {
         let mid_x = ((GRID_WIDTH / 2) | 1) as i64;  // Ensure odd position
-        Quorridor {
+        let mut game = Quorridor {
             player_pieces: [
                 Piece { x: mid_x, y: 1 },   // Player 0 starts at bottom middle
                 Piece { x: mid_x, y: (GRID_HEIGHT - 2) as i64 }   // Player 1 starts at top middle
@@ -252,7 +309,10 @@ impl Default for Quorridor {
             active_player: 0,
             grid: [[false; GRID_WIDTH]; GRID_HEIGHT],  // No walls initially
             walls_remaining: [10, 10],
-        }
+            zobrist: 0,
+        };
+        game.recompute_hash();
+        game
     }
 }
 
@@ -398,4 +458,92 @@ pub fn has_path_to_goal(game: &Quorridor, player_idx: usize) -> bool {
     }
     
     false
-}
\ No newline at end of file
+}
+// Grid offset applied to the active pawn by a movement move (None for walls)
+pub fn move_delta(mov: &crate::Move) -> Option<(i64, i64)> {
+    match mov {
+        crate::Move::Up => Some((0, -2)),
+        crate::Move::Down => Some((0, 2)),
+        crate::Move::Left => Some((-2, 0)),
+        crate::Move::Right => Some((2, 0)),
+        crate::Move::UpJump => Some((0, -4)),
+        crate::Move::DownJump => Some((0, 4)),
+        crate::Move::LeftJump => Some((-4, 0)),
+        crate::Move::RightJump => Some((4, 0)),
+        crate::Move::UpLeft => Some((-2, -2)),
+        crate::Move::UpRight => Some((2, -2)),
+        crate::Move::DownLeft => Some((-2, 2)),
+        crate::Move::DownRight => Some((2, 2)),
+        crate::Move::LeftUp => Some((-2, -2)),
+        crate::Move::LeftDown => Some((-2, 2)),
+        crate::Move::RightUp => Some((2, -2)),
+        crate::Move::RightDown => Some((2, 2)),
+        crate::Move::PlaceWall(..) => None,
+    }
+}
+
+// Sentinel distance for squares that cannot reach the goal row
+pub const UNREACHABLE: usize = usize::MAX;
+
+pub fn distance_to_goal_map(game: &Quorridor, player_idx: usize) -> [[usize; GRID_WIDTH]; GRID_HEIGHT] {
+    use std::collections::VecDeque;
+
+    // Multi-source BFS outward from every square on the goal row, ignoring pawns
+    let goal_y = if player_idx == 0 { (GRID_HEIGHT - 2) as i64 } else { 1 };
+    let mut distances = [[UNREACHABLE; GRID_WIDTH]; GRID_HEIGHT];
+    let mut queue = VecDeque::with_capacity(81);
+
+    for x in (1..GRID_WIDTH as i64 - 1).step_by(2) {
+        distances[goal_y as usize][x as usize] = 0;
+        queue.push_back((x, goal_y));
+    }
+
+    while let Some((x, y)) = queue.pop_front() {
+        let d = distances[y as usize][x as usize];
+        for (dx, dy) in [(2, 0), (-2, 0), (0, 2), (0, -2)] {
+            let nx = x + dx;
+            let ny = y + dy;
+
+            if nx < 1 || nx > (GRID_WIDTH - 2) as i64 || ny < 1 || ny > (GRID_HEIGHT - 2) as i64 {
+                continue;
+            }
+            if distances[ny as usize][nx as usize] != UNREACHABLE {
+                continue;
+            }
+            if game.wall_collision((x + nx) / 2, (y + ny) / 2) {
+                continue;
+            }
+
+            distances[ny as usize][nx as usize] = d + 1;
+            queue.push_back((nx, ny));
+        }
+    }
+
+    distances
+}
+
+// One shortest route to the goal row as pawn squares, starting at the player's square
+pub fn shortest_path_route(game: &Quorridor, player_idx: usize) -> Option<Vec<(i64, i64)>> {
+    let distances = distance_to_goal_map(game, player_idx);
+    let start = game.player_pieces[player_idx];
+    let mut current = (start.x, start.y);
+    if distances[current.1 as usize][current.0 as usize] == UNREACHABLE {
+        return None;
+    }
+
+    let mut route = vec![current];
+    while distances[current.1 as usize][current.0 as usize] > 0 {
+        let d = distances[current.1 as usize][current.0 as usize];
+        let (x, y) = current;
+        current = [(2, 0), (-2, 0), (0, 2), (0, -2)].iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .find(|&(nx, ny)| {
+                nx >= 1 && nx <= (GRID_WIDTH - 2) as i64 && ny >= 1 && ny <= (GRID_HEIGHT - 2) as i64
+                    && distances[ny as usize][nx as usize] == d - 1
+                    && !game.wall_collision((x + nx) / 2, (y + ny) / 2)
+            })
+            .unwrap();
+        route.push(current);
+    }
+    Some(route)
+}
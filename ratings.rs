@@ -0,0 +1,281 @@
+// Rating ladder for tournaments: a Bradley-Terry fit over every recorded game,
+// reported on the Elo scale, and a sqlite store for players, games and ratings.
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashMap;
+
+use crate::game_record::GameRecord;
+
+// Each pair that has met also gets one virtual draw, so an unbeaten player keeps a finite rating
+const PRIOR_DRAWS: f64 = 1.0;
+const FIT_ITERATIONS: usize = 2000;
+const FIT_TOLERANCE: f64 = 1e-9;
+
+// Natural-log strength to Elo
+const ELO_PER_NAT: f64 = 400.0 / std::f64::consts::LN_10;
+
+// Results between two players, from `a`'s side
+#[derive(Clone, Debug, PartialEq)]
+pub struct PairResult {
+    pub a: String,
+    pub b: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl PairResult {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rating {
+    pub name: String,
+    pub elo: f64,
+    pub stderr: f64,  // Standard error of `elo`
+    pub games: u32,
+}
+
+// Fit Bradley-Terry strengths with the minorization-maximization updates of
+// Hunter (2004), draws counting half a win for each side. Ratings are shifted so
+// that `anchor` sits at 0, or their mean if the anchor did not play.
+pub fn fit_ratings(results: &[PairResult], anchor: Option<&str>) -> Vec<Rating> {
+    let mut names: Vec<String> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for result in results {
+        for name in [&result.a, &result.b] {
+            if !index.contains_key(name) {
+                index.insert(name.clone(), names.len());
+                names.push(name.clone());
+            }
+        }
+    }
+    let n = names.len();
+
+    // games[i][j] and score[i] include the virtual draws
+    let mut games = vec![vec![0.0; n]; n];
+    let mut score = vec![0.0; n];
+    let mut real_games = vec![0u32; n];
+    for result in results.iter().filter(|r| r.games() > 0 && r.a != r.b) {
+        let (i, j) = (index[&result.a], index[&result.b]);
+        let played = result.games() as f64 + PRIOR_DRAWS;
+        games[i][j] += played;
+        games[j][i] += played;
+        score[i] += result.wins as f64 + 0.5 * (result.draws as f64 + PRIOR_DRAWS);
+        score[j] += result.losses as f64 + 0.5 * (result.draws as f64 + PRIOR_DRAWS);
+        real_games[i] += result.games();
+        real_games[j] += result.games();
+    }
+
+    let mut strength = vec![1.0; n];
+    for _ in 0..FIT_ITERATIONS {
+        let mut next: Vec<f64> = (0..n).map(|i| {
+            let denominator: f64 = (0..n)
+                .filter(|&j| games[i][j] > 0.0)
+                .map(|j| games[i][j] / (strength[i] + strength[j]))
+                .sum();
+            if denominator > 0.0 { score[i] / denominator } else { strength[i] }
+        }).collect();
+        // Strengths are only defined up to a common factor; keep their geometric mean at 1
+        let log_mean = next.iter().map(|s| s.ln()).sum::<f64>() / n as f64;
+        for s in next.iter_mut() {
+            *s /= log_mean.exp();
+        }
+        let change = next.iter().zip(&strength).map(|(a, b)| (a.ln() - b.ln()).abs()).fold(0.0, f64::max);
+        strength = next;
+        if change < FIT_TOLERANCE {
+            break;
+        }
+    }
+
+    let offset = anchor.and_then(|name| index.get(name)).map_or(0.0, |&i| strength[i].ln());
+    (0..n).map(|i| {
+        // Inverse of the diagonal of the Fisher information
+        let information: f64 = (0..n)
+            .filter(|&j| games[i][j] > 0.0)
+            .map(|j| {
+                let p = strength[i] / (strength[i] + strength[j]);
+                games[i][j] * p * (1.0 - p)
+            })
+            .sum();
+        Rating {
+            name: names[i].clone(),
+            elo: (strength[i].ln() - offset) * ELO_PER_NAT,
+            stderr: if information > 0.0 { ELO_PER_NAT / information.sqrt() } else { f64::INFINITY },
+            games: real_games[i],
+        }
+    }).collect()
+}
+
+// Players, games and fitted ratings of a tournament, persisted between runs
+pub struct RatingsDb {
+    conn: Connection,
+}
+
+impl RatingsDb {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        RatingsDb::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        RatingsDb::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS players (
+                name TEXT PRIMARY KEY,
+                spec TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS games (
+                id INTEGER PRIMARY KEY,
+                player0 TEXT NOT NULL REFERENCES players(name),
+                player1 TEXT NOT NULL REFERENCES players(name),
+                winner INTEGER,
+                plies INTEGER NOT NULL,
+                record TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS ratings (
+                name TEXT PRIMARY KEY REFERENCES players(name),
+                elo REAL NOT NULL,
+                stderr REAL NOT NULL,
+                games INTEGER NOT NULL
+            );"
+        )?;
+        Ok(RatingsDb { conn })
+    }
+
+    pub fn add_player(&self, name: &str, spec: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO players (name, spec) VALUES (?1, ?2) ON CONFLICT(name) DO UPDATE SET spec = ?2",
+            params![name, spec],
+        )?;
+        Ok(())
+    }
+
+    pub fn player_spec(&self, name: &str) -> rusqlite::Result<Option<String>> {
+        self.conn.query_row("SELECT spec FROM players WHERE name = ?1", params![name], |row| row.get(0)).optional()
+    }
+
+    // `record.players` must hold player names already added with add_player
+    pub fn add_game(&self, record: &GameRecord) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO games (player0, player1, winner, plies, record) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![record.players[0], record.players[1], record.winner.map(|w| w as i64), record.moves.len() as i64, record.to_json_line()],
+        )?;
+        Ok(())
+    }
+
+    pub fn games_played(&self, name: &str) -> rusqlite::Result<u32> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM games WHERE player0 = ?1 OR player1 = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+    }
+
+    // Every stored game, oldest first
+    pub fn records(&self) -> rusqlite::Result<Vec<GameRecord>> {
+        let mut statement = self.conn.prepare("SELECT record FROM games ORDER BY id")?;
+        let rows = statement.query_map([], |row| {
+            let json: String = row.get(0)?;
+            serde_json::from_str(&json).map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+        })?;
+        rows.collect()
+    }
+
+    // Results between every pair that has played, each pair once with names in sorted order
+    pub fn pair_results(&self) -> rusqlite::Result<Vec<PairResult>> {
+        let mut statement = self.conn.prepare("SELECT player0, player1, winner FROM games")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<i64>>(2)?))
+        })?;
+
+        let mut pairs: HashMap<(String, String), PairResult> = HashMap::new();
+        for row in rows {
+            let (player0, player1, winner) = row?;
+            let swapped = player1 < player0;
+            let (a, b) = if swapped { (player1, player0) } else { (player0, player1) };
+            let result = pairs.entry((a.clone(), b.clone()))
+                .or_insert(PairResult { a, b, wins: 0, draws: 0, losses: 0 });
+            match winner.map(|w| (w == 0) != swapped) {
+                Some(true) => result.wins += 1,
+                Some(false) => result.losses += 1,
+                None => result.draws += 1,
+            }
+        }
+        let mut results: Vec<PairResult> = pairs.into_values().collect();
+        results.sort_by(|x, y| (&x.a, &x.b).cmp(&(&y.a, &y.b)));
+        Ok(results)
+    }
+
+    pub fn store_ratings(&mut self, ratings: &[Rating]) -> rusqlite::Result<()> {
+        let transaction = self.conn.transaction()?;
+        transaction.execute("DELETE FROM ratings", [])?;
+        for rating in ratings {
+            transaction.execute(
+                "INSERT INTO ratings (name, elo, stderr, games) VALUES (?1, ?2, ?3, ?4)",
+                params![rating.name, rating.elo, rating.stderr, rating.games],
+            )?;
+        }
+        transaction.commit()
+    }
+
+    // Stored ratings, strongest first
+    pub fn ratings(&self) -> rusqlite::Result<Vec<Rating>> {
+        let mut statement = self.conn.prepare("SELECT name, elo, stderr, games FROM ratings ORDER BY elo DESC")?;
+        let rows = statement.query_map([], |row| {
+            Ok(Rating { name: row.get(0)?, elo: row.get(1)?, stderr: row.get(2)?, games: row.get(3)? })
+        })?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_record::Termination;
+
+    fn pair(a: &str, b: &str, wins: u32, draws: u32, losses: u32) -> PairResult {
+        PairResult { a: a.to_string(), b: b.to_string(), wins, draws, losses }
+    }
+
+    #[test]
+    fn test_fit_orders_players_and_keeps_unbeaten_finite() {
+        let results = [
+            pair("random", "shortest", 0, 0, 20),
+            pair("shortest", "gen1", 5, 0, 15),
+            pair("random", "gen1", 0, 0, 10),
+        ];
+        let ratings = fit_ratings(&results, Some("random"));
+        let elo = |name: &str| ratings.iter().find(|r| r.name == name).unwrap().elo;
+        assert_eq!(elo("random"), 0.0);
+        assert!(elo("shortest") > 0.0);
+        assert!(elo("gen1") > elo("shortest"));
+        assert!(ratings.iter().all(|r| r.elo.is_finite() && r.stderr.is_finite()));
+    }
+
+    #[test]
+    fn test_db_aggregates_games_from_both_seats() {
+        let mut db = RatingsDb::open_in_memory().unwrap();
+        db.add_player("a", "random").unwrap();
+        db.add_player("b", "shortest").unwrap();
+        let game = |players: [&str; 2], winner| GameRecord {
+            players: players.map(String::from),
+            moves: Vec::new(),
+            winner,
+            termination: Termination::Goal,
+        };
+        db.add_game(&game(["a", "b"], Some(0))).unwrap();
+        db.add_game(&game(["b", "a"], Some(0))).unwrap();
+        db.add_game(&game(["b", "a"], None)).unwrap();
+
+        assert_eq!(db.pair_results().unwrap(), vec![pair("a", "b", 1, 1, 1)]);
+        assert_eq!(db.games_played("a").unwrap(), 3);
+
+        let ratings = fit_ratings(&db.pair_results().unwrap(), None);
+        db.store_ratings(&ratings).unwrap();
+        assert_eq!(db.ratings().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,104 @@
+use mcts::GameState;
+use std::env;
+use std::fs;
+
+use quorridor::board_svg::{Annotations, DEFAULT_FRAME_MS, HIGHLIGHT_COLOR, position_svg, record_animated_svg, record_gif, svg_to_png};
+use quorridor::game_record::{GameRecord, Termination, load_records};
+use quorridor::moves::Move;
+use quorridor::notation::parse_move;
+use quorridor::quorridor::Quorridor;
+
+fn print_usage() {
+    println!("Usage: render (--moves \"e2 e8 ...\" | --record FILE [--game N]) [options] --out FILE");
+    println!("  Draws a position or a whole game; the format follows the extension of FILE:");
+    println!("  .svg or .png for the position after --ply N (default the last), .svg or .gif with --animate.");
+    println!("Options:");
+    println!("  --paths              Overlay shortest routes and critical edges");
+    println!("  --arrows M1,M2,...   Mark moves for the side to move (pawn moves as arrows, walls highlighted)");
+    println!("  --last               Mark the move that led to the position");
+    println!("  --animate            Step through every position of the game");
+    println!("  --frame-ms MS        Time per ply in animations (default {})", DEFAULT_FRAME_MS);
+    println!("  --scale X            Pixel scale for PNG and GIF output (default 1)");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|a| a == "--help") {
+        print_usage();
+        return;
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let arg = |flag: &str| args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let flag = |flag: &str| args.iter().any(|a| a == flag);
+    let number = |name: &str| -> Result<Option<f64>, String> {
+        arg(name).map(|v| v.parse::<f64>().map_err(|_| format!("{} takes a number, got '{}'", name, v))).transpose()
+    };
+
+    let record = if let Some(path) = arg("--record") {
+        let records = load_records(&path)?;
+        let index = number("--game")?.map_or(1, |n| n as usize);
+        records.get(index.wrapping_sub(1)).cloned()
+            .ok_or_else(|| format!("{} has {} games, no game {}", path, records.len(), index))?
+    } else if let Some(moves) = arg("--moves") {
+        GameRecord {
+            players: ["player 0".to_string(), "player 1".to_string()],
+            moves: moves.split_whitespace().map(String::from).collect(),
+            winner: None,
+            termination: Termination::MaxPlies,
+        }
+    } else {
+        GameRecord { players: Default::default(), moves: Vec::new(), winner: None, termination: Termination::MaxPlies }
+    };
+    let out = arg("--out").ok_or("--out FILE is required")?;
+    let extension = out.rsplit('.').next().unwrap_or("").to_lowercase();
+    let scale = number("--scale")?.unwrap_or(1.0) as f32;
+    let frame_ms = number("--frame-ms")?.map_or(DEFAULT_FRAME_MS, |ms| ms as u32);
+    let paths = flag("--paths");
+
+    let bytes = if flag("--animate") {
+        match extension.as_str() {
+            "svg" => record_animated_svg(&record, frame_ms, paths)?.into_bytes(),
+            "gif" => record_gif(&record, frame_ms, scale, paths)?,
+            _ => return Err("animations are written as .svg or .gif".to_string()),
+        }
+    } else {
+        let moves = record.parse_moves()?;
+        let ply = number("--ply")?.map_or(moves.len(), |n| n as usize).min(moves.len());
+        let mut state = Quorridor::default();
+        for mov in &moves[..ply.saturating_sub(1)] {
+            state.make_move(mov);
+        }
+        let mut annotations = Annotations { paths, ..Default::default() };
+        if ply > 0 {
+            if flag("--last") {
+                annotations.add_move(&state, &moves[ply - 1], HIGHLIGHT_COLOR);
+            }
+            state.make_move(&moves[ply - 1]);
+            annotations.caption = Some(format!("after {}. {}", ply, record.moves[ply - 1]));
+        }
+        for text in arg("--arrows").iter().flat_map(|list| list.split(',')) {
+            let mov: Move = parse_move(&state, text)
+                .filter(|mov| state.legal_moves().contains(mov))
+                .ok_or_else(|| format!("'{}' is not a legal move here", text))?;
+            annotations.add_move(&state, &mov, "#264653");
+        }
+        let svg = position_svg(&state, &annotations);
+        match extension.as_str() {
+            "svg" => svg.into_bytes(),
+            "png" => svg_to_png(&svg, scale)?,
+            _ => return Err("positions are written as .svg or .png".to_string()),
+        }
+    };
+    fs::write(&out, bytes).map_err(|e| format!("{}: {}", out, e))?;
+    println!("Wrote {}", out);
+    Ok(())
+}
@@ -0,0 +1,239 @@
+// Reviewing a stored game: every position of a record with the engine's
+// evaluation and best move, stepped through with the keyboard in the terminal
+// UI, and each move marked with its class from the analysis, so blunders stand out.
+use crossterm::event::KeyCode;
+use mcts::GameState;
+use std::io;
+
+use crate::analysis::{MoveAnalysis, MoveClass, PositionEval, analyze_move, evaluate_position};
+use crate::engine::Engine;
+use crate::game_record::GameRecord;
+use crate::moves::Move;
+use crate::quorridor::{Quorridor, move_delta};
+use crate::time_control::SearchLimits;
+use crate::tui::{Selection, Tui};
+
+pub struct Replay {
+    pub record: GameRecord,
+    pub moves: Vec<Move>,
+    pub positions: Vec<Quorridor>,  // positions[ply] is reached after `ply` moves
+    pub evals: Vec<Option<PositionEval>>,
+    pub ply: usize,
+}
+
+impl Replay {
+    pub fn new(record: GameRecord) -> Result<Self, String> {
+        let moves = record.parse_moves()?;
+        let mut positions = vec![Quorridor::default()];
+        for mov in &moves {
+            let mut next = positions.last().unwrap().clone();
+            next.make_move(mov);
+            positions.push(next);
+        }
+        let evals = vec![None; positions.len()];
+        Ok(Replay { record, moves, positions, evals, ply: 0 })
+    }
+
+    pub fn last_ply(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn state(&self) -> &Quorridor {
+        &self.positions[self.ply]
+    }
+
+    pub fn jump(&mut self, ply: usize) {
+        self.ply = ply.min(self.last_ply());
+    }
+
+    // Search the position after `ply` moves, unless already done
+    pub fn evaluate(&mut self, ply: usize, engine: &mut dyn Engine, limits: &SearchLimits) {
+        if self.evals[ply].is_some() {
+            return;
+        }
+        self.evals[ply] = Some(evaluate_position(&self.positions[0], &self.moves[..ply], engine, limits));
+    }
+
+    fn analyze_move(&self, index: usize) -> Option<MoveAnalysis> {
+        analyze_move(&self.positions[0], &self.moves, &self.evals, index)
+    }
+
+    pub fn is_blunder(&self, index: usize) -> bool {
+        self.analyze_move(index).is_some_and(|analysis| analysis.class == MoveClass::Blunder)
+    }
+
+    // The ply reached by the next blunder after the current position, or the
+    // previous one before it
+    pub fn find_blunder(&self, forward: bool) -> Option<usize> {
+        let ply = self.ply;
+        if forward {
+            (ply..self.last_ply()).find(|&i| self.is_blunder(i)).map(|i| i + 1)
+        } else {
+            (0..ply.saturating_sub(1)).rev().find(|&i| self.is_blunder(i)).map(|i| i + 1)
+        }
+    }
+
+    // Moves up to the current position, marked "?!", "?" or "??" by class
+    pub fn annotated_moves(&self) -> Vec<String> {
+        (0..self.ply)
+            .map(|i| {
+                let symbol = self.analyze_move(i).map_or("", |analysis| analysis.class.symbol());
+                format!("{}{}", self.record.moves[i], symbol)
+            })
+            .collect()
+    }
+
+    // What happened on the move into the current position
+    pub fn describe(&self) -> String {
+        if self.ply == 0 {
+            return format!("Start position, {} plies", self.last_ply());
+        }
+        let index = self.ply - 1;
+        let mover = self.positions[index].active_player;
+        let mut text = format!("Ply {}/{}: {} by player {}", self.ply, self.last_ply(), self.record.moves[index], mover);
+        if let Some(analysis) = self.analyze_move(index) {
+            text += &format!(", win {:.0}% -> {:.0}% - {}", analysis.eval_before * 100.0, analysis.eval_after * 100.0, analysis.class.name());
+            if analysis.class != MoveClass::Best && let Some(best) = analysis.best_move {
+                text += &format!(", {} was best", best);
+            }
+        }
+        text
+    }
+}
+
+// How a move is shown on the board: its destination square or the wall
+fn move_selection(state: &Quorridor, mov: &Move) -> Selection {
+    match mov {
+        Move::PlaceWall(x, y, orientation) => Selection::Wall(*x, *y, *orientation),
+        _ => {
+            let piece = state.player_pieces[state.active_player];
+            let (dx, dy) = move_delta(mov).unwrap();
+            Selection::Square(piece.x + dx, piece.y + dy)
+        }
+    }
+}
+
+// Search every position of the replay, showing progress; false if the user quit
+pub fn evaluate_all(tui: &mut Tui, replay: &mut Replay, engine: &mut dyn Engine, limits: &SearchLimits) -> io::Result<bool> {
+    for ply in 0..=replay.last_ply() {
+        if tui.pause(std::time::Duration::ZERO)? {
+            return Ok(false);
+        }
+        tui.info.messages = vec![format!("Evaluating ply {}/{}...", ply, replay.last_ply())];
+        tui.draw(&replay.positions[ply], None)?;
+        replay.evaluate(ply, engine, limits);
+    }
+    tui.info.messages.clear();
+    Ok(true)
+}
+
+// Step through the replay until the user quits. The engine's best move in the
+// current position is shown on the board.
+pub fn browse(tui: &mut Tui, replay: &mut Replay) -> io::Result<()> {
+    tui.info.help = vec![
+        "left/right: step   home/end: first/last".to_string(),
+        "b/B: next/previous blunder   g: go to ply".to_string(),
+        "p: show paths   q: quit".to_string(),
+    ];
+    // Digits typed after 'g'
+    let mut target: Option<String> = None;
+    loop {
+        let eval = replay.evals[replay.ply].as_ref();
+        tui.info.search = eval.and_then(|eval| eval.search.clone());
+        tui.info.moves = replay.annotated_moves();
+        tui.info.messages = vec![replay.describe()];
+        if let Some(digits) = &target {
+            tui.message(format!("Go to ply: {}_", digits));
+        }
+        let best = tui.info.search.as_ref().and_then(|search| search.best_move.clone());
+        let selection = best.map(|mov| move_selection(replay.state(), &mov));
+        tui.draw(replay.state(), selection)?;
+
+        let key = tui.read_key()?;
+        if let Some(digits) = target.as_mut() {
+            match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() => digits.push(c),
+                KeyCode::Backspace => {
+                    digits.pop();
+                }
+                KeyCode::Enter => {
+                    if let Ok(ply) = digits.parse() {
+                        replay.jump(ply);
+                    }
+                    target = None;
+                }
+                _ => target = None,
+            }
+            continue;
+        }
+        match key.code {
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') => replay.jump(replay.ply + 1),
+            KeyCode::Left | KeyCode::Char('h') => replay.jump(replay.ply.saturating_sub(1)),
+            KeyCode::Home => replay.jump(0),
+            KeyCode::End => replay.jump(replay.last_ply()),
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                match replay.find_blunder(key.code == KeyCode::Char('b')) {
+                    Some(ply) => replay.jump(ply),
+                    None => continue,
+                }
+            }
+            KeyCode::Char('g') => target = Some(String::new()),
+            KeyCode::Char('p') => tui.info.show_paths = !tui.info.show_paths,
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabeta::AlphaBeta;
+    use crate::engine::AlphaBetaEngine;
+    use crate::game_record::test_record as record;
+
+    #[test]
+    fn test_stepping_and_blunders() {
+        let mut replay = Replay::new(record(&["e2", "e8", "e3"])).unwrap();
+        assert_eq!(replay.positions.len(), 4);
+        replay.jump(10);
+        assert_eq!(replay.ply, 3);
+        assert_eq!(replay.state().player_pieces[0].y, 5);
+
+        let eval = |p: f64| Some(PositionEval { win_probability: p, search: None });
+        // Player 0 holds 60%, then player 1 drops from 40% to 10%
+        replay.evals = vec![eval(0.6), eval(0.4), eval(0.9), eval(0.2)];
+        assert!(!replay.is_blunder(0));
+        assert!(replay.is_blunder(1));
+        assert_eq!(replay.annotated_moves(), vec!["e2", "e8??", "e3?!"]);
+        assert_eq!(replay.find_blunder(false), Some(2));
+        replay.jump(0);
+        assert_eq!(replay.find_blunder(true), Some(2));
+        replay.jump(2);
+        assert!(replay.describe().contains("e8 by player 1, win 40% -> 10% - blunder"));
+        assert_eq!(replay.find_blunder(true), None);
+
+        assert!(Replay::new(record(&["e2", "e3"])).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_uses_engine_and_finished_games() {
+        let mut replay = Replay::new(record(&["e2"])).unwrap();
+        let mut engine = AlphaBetaEngine::new(AlphaBeta::new());
+        let limits = SearchLimits { depth: Some(1), ..Default::default() };
+        replay.evaluate(1, &mut engine, &limits);
+        let search = replay.evals[1].as_ref().unwrap().search.as_ref().unwrap();
+        assert!(replay.positions[1].legal_moves().contains(search.best_move.as_ref().unwrap()));
+
+        // Player 0 one step from the goal walks in; player 1 is left lost
+        let mut moves = Vec::new();
+        for ply in 0..15 {
+            moves.push(if ply % 2 == 0 { format!("e{}", ply / 2 + 2) } else { ["d9", "c9"][ply / 2 % 2].to_string() });
+        }
+        let mut replay = Replay::new(record(&moves.iter().map(String::as_str).collect::<Vec<_>>())).unwrap();
+        let last = replay.last_ply();
+        assert_eq!(replay.positions[last].winner(), Some(0));
+        replay.evaluate(last, &mut engine, &limits);
+        assert_eq!(replay.evals[last].as_ref().unwrap().win_probability, 0.0);
+    }
+}
@@ -0,0 +1,158 @@
+use mcts::GameState;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::moves::Move;
+use crate::quorridor::{Quorridor, shortest_path_to_goal, distance_to_goal_map, move_delta, GRID_HEIGHT};
+
+#[derive(Clone, Debug)]
+pub struct RolloutConfig {
+    pub rollouts_per_eval: usize,   // Playouts averaged into a single leaf evaluation
+    pub wall_probability: f64,      // Chance per ply of trying a wall instead of stepping
+    pub wall_candidates: usize,     // Random walls sampled when trying a wall
+    pub step_randomness: f64,       // Chance of a random pawn move instead of the shortest-path step
+    pub max_plies: usize,           // Rollouts longer than this are scored as a draw
+}
+
+impl Default for RolloutConfig {
+    fn default() -> Self {
+        RolloutConfig {
+            rollouts_per_eval: 2,
+            wall_probability: 0.1,
+            wall_candidates: 8,
+            step_randomness: 0.1,
+            max_plies: 200,
+        }
+    }
+}
+
+// Pick a pawn move, mostly the step that follows the active player's shortest path
+pub fn rollout_step<R: Rng>(state: &Quorridor, config: &RolloutConfig, rng: &mut R) -> Option<Move> {
+    let mut pawn_moves = state.get_movement_moves();
+    pawn_moves.extend(state.get_special_moves());
+    if pawn_moves.is_empty() {
+        return None;
+    }
+    if rng.gen_bool(config.step_randomness) {
+        return pawn_moves.choose(rng).cloned();
+    }
+
+    let distances = distance_to_goal_map(state, state.active_player);
+    let piece = state.player_pieces[state.active_player];
+    let distance_after = |mov: &Move| -> usize {
+        let (dx, dy) = move_delta(mov).unwrap();
+        distances[(piece.y + dy) as usize][(piece.x + dx) as usize]
+    };
+
+    let best = pawn_moves.iter().map(&distance_after).min().unwrap();
+    let best_moves: Vec<&Move> = pawn_moves.iter().filter(|m| distance_after(m) == best).collect();
+    best_moves.choose(rng).map(|m| (*m).clone())
+}
+
+// Sample a few walls and keep the one that lengthens the opponent's path the most
+pub fn rollout_wall<R: Rng>(state: &Quorridor, config: &RolloutConfig, rng: &mut R) -> Option<Move> {
+    let walls = state.get_wall_moves();
+    if walls.is_empty() {
+        return None;
+    }
+
+    let opponent = 1 - state.active_player;
+    let opponent_before = shortest_path_to_goal(state, opponent)?;
+    let mut best: Option<(usize, Move)> = None;
+
+    for wall in walls.choose_multiple(rng, config.wall_candidates) {
+        let mut next = state.clone();
+        next.make_move(wall);
+        let (Some(_), Some(theirs)) = (
+            shortest_path_to_goal(&next, state.active_player),
+            shortest_path_to_goal(&next, opponent),
+        ) else {
+            continue;  // Walls that seal off a player are never useful
+        };
+        if theirs > opponent_before && best.as_ref().is_none_or(|(gain, _)| theirs - opponent_before > *gain) {
+            best = Some((theirs - opponent_before, wall.clone()));
+        }
+    }
+
+    best.map(|(_, wall)| wall)
+}
+
+// Play the position out with the rollout policy and return the winner, if any
+pub fn rollout<R: Rng>(state: &Quorridor, config: &RolloutConfig, rng: &mut R) -> Option<usize> {
+    let mut state = state.clone();
+
+    for _ in 0..config.max_plies {
+        if state.player_pieces[0].y >= (GRID_HEIGHT - 2) as i64 {
+            return Some(0);
+        }
+        if state.player_pieces[1].y <= 1 {
+            return Some(1);
+        }
+
+        let wall = if state.walls_remaining[state.active_player] > 0 && rng.gen_bool(config.wall_probability) {
+            rollout_wall(&state, config, rng)
+        } else {
+            None
+        };
+        let mov = wall.or_else(|| rollout_step(&state, config, rng))?;
+        state.make_move(&mov);
+    }
+
+    None
+}
+
+// Average rollout outcome from player 0's perspective, in [-1, 1]
+pub fn rollout_value<R: Rng>(state: &Quorridor, config: &RolloutConfig, rng: &mut R) -> f64 {
+    let n = config.rollouts_per_eval.max(1);
+    let total: f64 = (0..n)
+        .map(|_| match rollout(state, config, rng) {
+            Some(0) => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
+        })
+        .sum();
+    total / n as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endgame::test_race;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    // Deterministic policy: always the shortest-path step, never a wall
+    fn greedy_config() -> RolloutConfig {
+        RolloutConfig { rollouts_per_eval: 4, wall_probability: 0.0, step_randomness: 0.0, ..Default::default() }
+    }
+
+    #[test]
+    fn test_rollout_step_and_wall() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(rollout_step(&Quorridor::default(), &greedy_config(), &mut rng), Some(Move::Down));
+
+        // Every sampled wall is scored, so the one kept must lengthen player 1's path
+        let game = Quorridor::default();
+        let config = RolloutConfig { wall_candidates: usize::MAX, ..Default::default() };
+        let wall = rollout_wall(&game, &config, &mut rng).unwrap();
+        let mut after = game.clone();
+        after.make_move(&wall);
+        assert!(shortest_path_to_goal(&after, 1) > shortest_path_to_goal(&game, 1));
+    }
+
+    #[test]
+    fn test_rollout_winner_and_value_sign() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let config = greedy_config();
+        // Player 0 one step from their goal and to move
+        let game = test_race((9, 15), (1, 15), 0);
+        assert_eq!(rollout(&game, &config, &mut rng), Some(0));
+        assert_eq!(rollout_value(&game, &config, &mut rng), 1.0);
+
+        // The value stays from player 0's view whoever is to move: player 1 one
+        // step from their goal with the move wins every rollout
+        let game = test_race((1, 1), (9, 3), 1);
+        assert_eq!(rollout(&game, &config, &mut rng), Some(1));
+        assert_eq!(rollout_value(&game, &config, &mut rng), -1.0);
+    }
+}
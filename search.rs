@@ -0,0 +1,119 @@
+use mcts::*;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::mcts_impl::MyMCTS;
+use crate::time_control::{SearchLimits, DEFAULT_PLAYOUTS};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum StopReason {
+    PlayoutLimit,
+    SoftDeadline,
+    HardDeadline,
+    BestMoveDecided,  // The runner-up could not catch up in the time left
+    DepthLimit,       // Alpha-beta finished its deepest iteration
+    Stopped,          // Engine::stop was called
+    NoSearch,         // Baseline bots pick their move without searching
+    Proven,           // The root position was solved
+    NodeLimit,        // The tree holds as many nodes as its spec allows
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchStats {
+    pub playouts: u64,
+    pub elapsed: Duration,
+    pub stop_reason: StopReason,
+}
+
+// Keep searching past the soft deadline while the runner-up has at least this
+// share of the best move's visits
+const CLOSE_CALL_RATIO: f64 = 0.7;
+// Roughly how long one batch of playouts runs before the limits are checked again
+const BATCH_TARGET: Duration = Duration::from_millis(20);
+
+// Visits of the two most searched root moves, inherited visits included
+fn top_two_visits(mcts: &MCTSManager<MyMCTS>) -> (u64, u64) {
+    let mut best = 0;
+    let mut second = 0;
+    for info in mcts.tree().root_node().moves() {
+        let visits = info.visits() + info.move_evaluation().visits;
+        if visits > best {
+            second = best;
+            best = visits;
+        } else if visits > second {
+            second = visits;
+        }
+    }
+    (best, second)
+}
+
+fn run_batch(mcts: &mut MCTSManager<MyMCTS>, n: u64, threads: usize) {
+    if threads > 1 {
+        mcts.playout_n_parallel(n as u32, threads);
+    } else {
+        mcts.playout_n(n);
+    }
+}
+
+// Run playouts on `mcts` until the limits are used up. Timed searches stop early
+// once the best root move can no longer be overtaken, and run past the soft
+// deadline while the top two moves are close.
+pub fn run_search(mcts: &mut MCTSManager<MyMCTS>, limits: &SearchLimits, threads: usize) -> SearchStats {
+    run_search_until(mcts, limits, threads, &AtomicBool::new(false))
+}
+
+// As run_search, but also returns early once `stop` is set
+pub fn run_search_until(mcts: &mut MCTSManager<MyMCTS>, limits: &SearchLimits, threads: usize, stop: &AtomicBool) -> SearchStats {
+    let start = Instant::now();
+    let budget = limits.budget(mcts.tree().root_state());
+    let max_playouts = match (limits.playouts, budget) {
+        (Some(n), _) => n,
+        (None, Some(_)) => u64::MAX,
+        (None, None) => DEFAULT_PLAYOUTS,
+    };
+
+    let mut playouts = 0;
+    let mut batch = 16 * threads.max(1) as u64;
+    let stop_reason = loop {
+        if stop.load(Ordering::Relaxed) {
+            break StopReason::Stopped;
+        }
+        let n = batch.min(max_playouts - playouts);
+        let batch_start = Instant::now();
+        run_batch(mcts, n, threads);
+        playouts += n;
+
+        // Aim the next batch at BATCH_TARGET using the rate of the last one
+        let batch_time = batch_start.elapsed().max(Duration::from_micros(100));
+        let rate = n as f64 / batch_time.as_secs_f64();
+        batch = ((rate * BATCH_TARGET.as_secs_f64()) as u64).clamp(threads.max(1) as u64, 100_000);
+
+        let elapsed = start.elapsed();
+        if mcts.tree().root_node().data().get().is_some() {
+            break StopReason::Proven;
+        }
+        if playouts >= max_playouts {
+            break StopReason::PlayoutLimit;
+        }
+        // Further playouts would not grow the tree
+        if mcts.tree().num_nodes() >= mcts.tree().spec().node_limit() {
+            break StopReason::NodeLimit;
+        }
+        let Some(budget) = budget else { continue };
+        if elapsed >= budget.hard {
+            break StopReason::HardDeadline;
+        }
+
+        let (best, second) = top_two_visits(mcts);
+        let playouts_left = rate * (budget.hard - elapsed).as_secs_f64();
+        if best > 0 && (best - second) as f64 > playouts_left {
+            break StopReason::BestMoveDecided;
+        }
+        if elapsed >= budget.soft && (second as f64) < best as f64 * CLOSE_CALL_RATIO {
+            break StopReason::SoftDeadline;
+        }
+    };
+
+    SearchStats { playouts, elapsed: start.elapsed(), stop_reason }
+}
@@ -0,0 +1,182 @@
+use mcts::*;
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+use crate::endgame::Proven;
+use crate::mcts_impl::{MyMCTS, child_result};
+use crate::moves::Move;
+use crate::quorridor::Quorridor;
+use crate::notation::{move_to_notation, line_to_notation};
+use crate::search::{SearchStats, StopReason};
+use crate::transposition::TableStatsSnapshot;
+
+// Evaluation units (1000 per step of path difference) that map to a ~73% win chance
+pub const WIN_PROBABILITY_SCALE: f64 = 1000.0;
+pub const PV_LENGTH: usize = 12;
+
+// Logistic mapping from a mean reward to the mover's chance of winning
+pub fn win_probability(q: f64) -> f64 {
+    1.0 / (1.0 + (-q / WIN_PROBABILITY_SCALE).exp())
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RootMoveStats {
+    #[serde(skip)]
+    pub mov: Move,
+    #[serde(rename = "move")]
+    pub notation: String,
+    pub visits: u64,
+    pub inherited_visits: u64,
+    pub q: Option<f64>,  // Mean reward for the side to move, inherited visits included; None for alpha-beta moves that failed low
+    pub win_probability: Option<f64>,
+    pub proven: Option<Proven>,  // For the side to move at the root
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchResult {
+    #[serde(skip)]
+    pub best_move: Option<Move>,
+    #[serde(rename = "best_move")]
+    pub best_move_notation: Option<String>,
+    #[serde(skip)]
+    pub principal_variation: Vec<Move>,
+    #[serde(rename = "principal_variation")]
+    pub principal_variation_notation: Vec<String>,
+    pub root_moves: Vec<RootMoveStats>,  // Most visited first
+    pub win_probability: f64,
+    pub playouts: u64,
+    pub inherited_visits: u64,
+    pub elapsed_ms: f64,
+    pub playouts_per_second: f64,  // Like `playouts`, counts nodes for alpha-beta
+    pub tree_size: usize,
+    pub stop_reason: StopReason,
+    pub transposition: Option<TableStatsSnapshot>,  // Filled in by callers that kept a stats handle
+    pub depth: Option<u32>,  // Last completed alpha-beta iteration; playouts then counts nodes
+    pub proven: Option<Proven>,  // Solved result of the root for the side to move
+}
+
+impl SearchResult {
+    pub fn from_mcts(mcts: &MCTSManager<MyMCTS>, stats: &SearchStats) -> Self {
+        let root_state = mcts.tree().root_state();
+
+        let mut root_moves: Vec<RootMoveStats> = mcts.tree().root_node().moves()
+            .map(|info| {
+                let prior = info.move_evaluation();
+                let visits = info.visits() + prior.visits;
+                let q = if visits == 0 { 0.0 } else { (info.sum_rewards() + prior.sum_rewards) as f64 / visits as f64 };
+                let proven = child_result(info).map(Proven::for_parent);
+                // A proven move is certain whatever its playouts averaged
+                let probability = match proven {
+                    Some(Proven::Win(_)) => 1.0,
+                    Some(Proven::Loss(_)) => 0.0,
+                    None => win_probability(q),
+                };
+                RootMoveStats {
+                    mov: info.get_move().clone(),
+                    notation: move_to_notation(root_state, info.get_move()),
+                    visits: info.visits(),
+                    inherited_visits: prior.visits,
+                    q: Some(q),
+                    win_probability: Some(probability),
+                    proven,
+                }
+            })
+            .collect();
+        root_moves.sort_by_key(|m| std::cmp::Reverse(m.visits + m.inherited_visits));
+
+        let best_move = mcts.best_move();
+        let principal_variation = mcts.principal_variation(PV_LENGTH);
+        let proven = mcts.tree().root_node().data().get();
+        let win_probability = match proven {
+            Some(Proven::Win(_)) => 1.0,
+            Some(Proven::Loss(_)) => 0.0,
+            None => best_move.as_ref()
+                .and_then(|best| root_moves.iter().find(|m| &m.mov == best))
+                .and_then(|m| m.win_probability)
+                .unwrap_or(0.5),
+        };
+
+        let elapsed = stats.elapsed.as_secs_f64();
+        let tree_size = mcts.tree().num_nodes();
+
+        SearchResult {
+            best_move_notation: best_move.as_ref().map(|m| move_to_notation(root_state, m)),
+            best_move,
+            principal_variation_notation: line_to_notation(root_state, &principal_variation),
+            principal_variation,
+            inherited_visits: root_moves.iter().map(|m| m.inherited_visits).sum(),
+            root_moves,
+            win_probability,
+            playouts: stats.playouts,
+            elapsed_ms: elapsed * 1000.0,
+            playouts_per_second: if elapsed > 0.0 { stats.playouts as f64 / elapsed } else { 0.0 },
+            tree_size,
+            stop_reason: stats.stop_reason,
+            transposition: None,
+            depth: None,
+            proven,
+        }
+    }
+
+    // Result for a move picked without a search, as the baseline bots do
+    pub fn without_search(state: &Quorridor, best_move: Option<Move>, elapsed: Duration) -> Self {
+        SearchResult {
+            best_move_notation: best_move.as_ref().map(|m| move_to_notation(state, m)),
+            principal_variation_notation: best_move.iter().map(|m| move_to_notation(state, m)).collect(),
+            principal_variation: best_move.iter().cloned().collect(),
+            best_move,
+            root_moves: Vec::new(),
+            win_probability: 0.5,
+            playouts: 0,
+            inherited_visits: 0,
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            playouts_per_second: 0.0,
+            tree_size: 0,
+            stop_reason: StopReason::NoSearch,
+            transposition: None,
+            depth: None,
+            proven: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("SearchResult is always serializable")
+    }
+}
+
+// Shown by the CLI: a summary line, the principal variation, and the top root moves
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Best move: {}   win probability {:.1}%",
+            self.best_move_notation.as_deref().unwrap_or("-"), self.win_probability * 100.0)?;
+        writeln!(f, "PV: {}", self.principal_variation_notation.join(" "))?;
+        if let Some(proven) = self.proven {
+            writeln!(f, "Proven: {}", proven)?;
+        }
+        match self.depth {
+            Some(depth) => writeln!(f, "Depth {}, {} nodes in {:.2}s, {:.0} nodes/s, {} table entries ({:?})",
+                depth, self.playouts, self.elapsed_ms / 1000.0, self.playouts_per_second, self.tree_size, self.stop_reason)?,
+            None => writeln!(f, "{} playouts (+{} inherited) in {:.2}s, {:.0} playouts/s, tree size {} ({:?})",
+                self.playouts, self.inherited_visits, self.elapsed_ms / 1000.0,
+                self.playouts_per_second, self.tree_size, self.stop_reason)?,
+        }
+        if let Some(table) = &self.transposition {
+            writeln!(f, "Transpositions: {:.1}% hit rate over {} lookups, {} stored of {} slots, {:.1}% of stores found a full bucket, {} key collisions",
+                table.hit_rate() * 100.0, table.lookups, table.stores, table.capacity, table.eviction_rate() * 100.0, table.collisions)?;
+        }
+        writeln!(f, "  {:<6} {:>8} {:>10} {:>10} {:>7}", "move", "visits", "inherited", "Q", "win%")?;
+        for m in self.root_moves.iter().take(8) {
+            write!(f, "  {:<6} {:>8} {:>10}", m.notation, m.visits, m.inherited_visits)?;
+            match (m.q, m.win_probability) {
+                (Some(q), Some(p)) => write!(f, " {:>10.1} {:>6.1}%", q, p * 100.0)?,
+                _ => write!(f, " {:>10} {:>7}", "-", "-")?,
+            }
+            match m.proven {
+                Some(proven) => writeln!(f, "  {}", proven)?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,16 @@
 use mcts::*;
-use mcts::tree_policy::*;
-use mcts::transposition_table::*;
 use std::fs::File;
 use std::io::{Write, BufWriter};
 use std::env;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use serde::{Serialize, Deserialize};
 
-use quorridor::{Quorridor, Move, mcts_impl::{MyMCTS, MyEvaluator}, Orientation, policy_network::PolicyNetwork, log_game_metrics, create_metrics_file};
+use quorridor::{Quorridor, Move, mcts_impl::MyEvaluator, game_record::{GameRecord, Termination, append_record}, notation::line_to_notation, opening_book::{OpeningBook, DEFAULT_BOOK_DEPTH, DEFAULT_MIN_GAMES}, engine::{Engine, MctsEngine, create_engine}, transposition::DEFAULT_TABLE_MB, time_control::SearchLimits, Orientation, policy_network::PolicyNetwork, rollout::RolloutConfig, move_filter::WallFilter, log_game_metrics, create_metrics_file};
+
+// Standard UCT exploration for data generation
+const SELFPLAY_EXPLORATION: f64 = 1.414;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct TrainingExample {
@@ -57,14 +60,27 @@ fn encode_move(mov: &Move) -> usize {
     }
 }
 
-fn play_self_play_game(game_number: usize, num_playouts: u32, evaluator: &MyEvaluator, use_parallel: bool, log_metrics: bool, metrics_file: &str, max_turns: usize) -> Vec<TrainingExample> {
-    let mut game = Quorridor::default();
+// Plays one game after the moves of `opening`, which produce no training examples
+fn play_self_play_game(game_number: usize, limits: &SearchLimits, engine: &mut dyn Engine, opening: &[Move], log_metrics: bool, metrics_file: &str, max_turns: usize) -> (Vec<TrainingExample>, GameRecord) {
+    let start = Quorridor::default();
+    let mut game = start.clone();
     let mut examples = Vec::new();
     let mut move_history = Vec::new();
+    let mut played = Vec::new();
+    engine.new_game();
     
     println!("Game {}: Starting self-play...", game_number);
+    if !opening.is_empty() {
+        println!("  Book opening: {}", line_to_notation(&start, opening).join(" "));
+    }
+    for mov in opening {
+        game.make_move(mov);
+        played.push(mov.clone());
+    }
+    let mut no_move = false;
     
     let mut turn = 0;
+    let mut total_inherited = 0;
     while !game.game_over() && turn < max_turns {
         turn += 1;
         
@@ -73,20 +89,10 @@ fn play_self_play_game(game_number: usize, num_playouts: u32, evaluator: &MyEval
             log_game_metrics(&game, metrics_file);
         }
         
-        // Run MCTS to get move distribution
-        let mut mcts = MCTSManager::new(
-            game.clone(),
-            MyMCTS,
-            evaluator.clone(),
-            UCTPolicy::new(1.414),  // Standard exploration
-            ApproxTable::new(8192)
-        );
-        
-        if use_parallel {
-            mcts.playout_n_parallel(num_playouts, 4);
-        } else {
-            mcts.playout_n(num_playouts as u64);
-        }
+        // Run the search to get the move distribution
+        engine.set_position(&start, &played);
+        let result = engine.search(limits);
+        total_inherited += result.inherited_visits;
         
         // Get visit counts for all moves (MCTS policy)
         let available_moves = game.available_moves();
@@ -94,7 +100,6 @@ fn play_self_play_game(game_number: usize, num_playouts: u32, evaluator: &MyEval
         let mut visit_counts = Vec::new();
         
         // Extract visit counts from MCTS tree
-        let tree = mcts.tree();
         for (i, mov) in available_moves.iter().enumerate() {
             move_indices.push(encode_move(mov));
             // For now, use dummy visit counts (would need MCTS API to get real ones)
@@ -124,12 +129,14 @@ fn play_self_play_game(game_number: usize, num_playouts: u32, evaluator: &MyEval
         
         examples.push(example);
         
-        // Make the move chosen by MCTS
-        if let Some(chosen_move) = mcts.best_move() {
+        // Make the move chosen by the search
+        if let Some(chosen_move) = result.best_move {
             move_history.push((game.active_player, chosen_move.clone()));
             game.make_move(&chosen_move);
+            played.push(chosen_move);
         } else {
             println!("  No moves available!");
+            no_move = true;
             break;
         }
         
@@ -140,6 +147,21 @@ fn play_self_play_game(game_number: usize, num_playouts: u32, evaluator: &MyEval
     }
     
     println!();
+    println!("  Visits inherited through tree reuse: {} ({:.1} per move)", total_inherited, total_inherited as f64 / turn.max(1) as f64);
+    
+    let termination = if game.game_over() {
+        Termination::Goal
+    } else if no_move {
+        Termination::NoMove
+    } else {
+        Termination::MaxPlies
+    };
+    let record = GameRecord {
+        players: [engine.name(), engine.name()],
+        moves: line_to_notation(&start, &played),
+        winner: if no_move { Some(1 - game.active_player) } else { game.winner() },
+        termination,
+    };
     
     // Determine winner
     let winner = if game.player_pieces[0].y >= (quorridor::GRID_HEIGHT - 2) as i64 {
@@ -148,7 +170,7 @@ fn play_self_play_game(game_number: usize, num_playouts: u32, evaluator: &MyEval
         1
     } else {
         println!("  Game ended without winner (turn limit)");
-        return Vec::new();  // Discard games that don't finish
+        return (Vec::new(), record);  // Discard games that don't finish
     };
     
     println!("  Game ended: Player {} wins in {} turns", winner, turn);
@@ -159,32 +181,114 @@ fn play_self_play_game(game_number: usize, num_playouts: u32, evaluator: &MyEval
         example.outcome = if example_player == winner { 1.0 } else { -1.0 };
     }
     
-    examples
+    (examples, record)
+}
+
+// Strength check for the wall filter: the filtered and the full search play each
+// other with equal limits, swapping sides every game
+fn compare_wall_filter(num_games: usize, limits: &SearchLimits, evaluator: &MyEvaluator, threads: usize, max_turns: usize) {
+    let mut full = MctsEngine::new(evaluator.with_wall_filter(None), SELFPLAY_EXPLORATION, threads, DEFAULT_TABLE_MB);
+    let mut filtered = MctsEngine::new(evaluator.with_wall_filter(Some(WallFilter::default())), SELFPLAY_EXPLORATION, threads, DEFAULT_TABLE_MB);
+    let (mut filtered_wins, mut full_wins, mut unfinished) = (0, 0, 0);
+
+    for game_num in 1..=num_games {
+        let filtered_seat = (game_num + 1) % 2;
+        let start = Quorridor::default();
+        let mut game = start.clone();
+        let mut played = Vec::new();
+        full.new_game();
+        filtered.new_game();
+        let mut turn = 0;
+        while !game.game_over() && turn < max_turns {
+            turn += 1;
+            let side: &mut dyn Engine = if game.active_player == filtered_seat { &mut filtered } else { &mut full };
+            side.set_position(&start, &played);
+            let Some(mov) = side.search(limits).best_move else { break };
+            game.make_move(&mov);
+            played.push(mov);
+        }
+
+        let winner = if game.player_pieces[0].y >= (quorridor::GRID_HEIGHT - 2) as i64 {
+            Some(0)
+        } else if game.player_pieces[1].y <= 1 {
+            Some(1)
+        } else {
+            None
+        };
+        match winner {
+            Some(w) if w == filtered_seat => filtered_wins += 1,
+            Some(_) => full_wins += 1,
+            None => unfinished += 1,
+        }
+        println!("Game {}: filtered search as player {}, {} after {} turns", game_num, filtered_seat,
+            match winner { Some(w) if w == filtered_seat => "filtered wins", Some(_) => "full wins", None => "unfinished" }, turn);
+    }
+
+    println!("\nFiltered {} - {} full ({} unfinished)", filtered_wins, full_wins, unfinished);
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+// The value after `flag`, if given, parsed as T
+fn parse_arg<T: FromStr>(args: &[String], flag: &str, what: &str) -> Result<Option<T>, String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.parse().map_err(|_| format!("{} takes {}, got '{}'", flag, what, v)))
+        .transpose()
+}
+
+fn run(args: &[String]) -> Result<(), String> {
     let use_network = args.contains(&"--network".to_string());
+    let use_rollout = args.contains(&"--rollout".to_string());
     let log_metrics = args.contains(&"--log-metrics".to_string());
+    let prune_walls = args.contains(&"--prune-walls".to_string());
+    let compare_prune = args.contains(&"--compare-prune".to_string());
+    let engine_name = args.iter().position(|a| a == "--engine")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    // These configure the MCTS evaluator, which other engines do not use
+    if let Some(name) = engine_name.as_deref().filter(|&name| name != "mcts")
+        && let Some(flag) = ["--network", "--rollout", "--prune-walls"].iter().find(|flag| args.contains(&flag.to_string())) {
+        return Err(format!("{} does not apply with --engine {}", flag, name));
+    }
+    
+    let depth = parse_arg::<u32>(args, "--depth", "a whole number")?;
     
     // Parse num_games, num_playouts, and max_turns from args
-    let num_games = args.iter()
-        .position(|a| a == "--games")
-        .and_then(|i| args.get(i + 1))
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(10);
+    let num_games = parse_arg::<usize>(args, "--games", "a whole number")?.unwrap_or(10);
+    
+    let num_playouts = parse_arg::<u32>(args, "--playouts", "a whole number")?.unwrap_or(1000);
+    
+    // A per-move time budget replaces the playout count when given
+    let move_time = parse_arg::<u64>(args, "--movetime", "a whole number of milliseconds")?.map(Duration::from_millis);
     
-    let num_playouts = args.iter()
-        .position(|a| a == "--playouts")
+    let max_turns = parse_arg::<usize>(args, "--max-turns", "a whole number")?.unwrap_or(200);
+    
+    // Finished games in move notation, e.g. for building an opening book
+    let records_path = args.iter()
+        .position(|a| a == "--records")
         .and_then(|i| args.get(i + 1))
-        .and_then(|s| s.parse::<u32>().ok())
-        .unwrap_or(1000);
+        .cloned();
     
-    let max_turns = args.iter()
-        .position(|a| a == "--max-turns")
+    // Each game starts from a line drawn from the book, so games don't all repeat the same opening
+    let book = args.iter()
+        .position(|a| a == "--book")
         .and_then(|i| args.get(i + 1))
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(200);
+        .and_then(|path| match OpeningBook::load(path) {
+            Ok(book) => Some(book),
+            Err(e) => {
+                eprintln!("Could not load opening book: {}", e);
+                None
+            }
+        });
+    let book_depth = parse_arg::<usize>(args, "--book-depth", "a whole number")?.unwrap_or(DEFAULT_BOOK_DEPTH);
     
     if log_metrics {
         create_metrics_file("selfplay_metrics.csv");
@@ -207,28 +311,62 @@ fn main() {
                 (MyEvaluator::new(), true)
             }
         }
+    } else if use_rollout {
+        println!("Using random-rollout evaluation");
+        (MyEvaluator::with_rollouts(RolloutConfig::default()), true)
     } else {
         println!("Using heuristic evaluation (use --network to enable neural network)");
         (MyEvaluator::new(), true)
     };
+    let evaluator = evaluator.with_wall_filter(prune_walls.then(WallFilter::default));
     
     println!("=== Quorridor Self-Play Data Generation ===\n");
     
     println!("Configuration:");
     println!("  Games: {}", num_games);
-    println!("  MCTS playouts per move: {}", num_playouts);
+    match move_time {
+        Some(t) => println!("  MCTS time per move: {}ms", t.as_millis()),
+        None => println!("  MCTS playouts per move: {}", num_playouts),
+    }
     println!("  Max turns per game: {}", max_turns);
     println!("  MCTS mode: {}", if use_parallel { "parallel (4 threads)" } else { "single-threaded" });
+    println!("  Wall filtering: {}", if prune_walls { "on" } else { "off" });
+    println!("  Engine: {}", engine_name.as_deref().unwrap_or("mcts"));
+    if let Some(book) = &book {
+        println!("  Opening book: {} positions, openings up to {} plies", book.positions.len(), book_depth);
+    }
     println!();
-    println!("Usage: selfplay [--games N] [--playouts N] [--max-turns N] [--network] [--log-metrics]");
+    println!("Usage: selfplay [--games N] [--playouts N] [--movetime MS] [--max-turns N] [--engine mcts|alphabeta] [--depth N] [--network] [--rollout] [--prune-walls] [--compare-prune] [--book FILE] [--book-depth N] [--records FILE] [--log-metrics]");
     println!();
     
+    let limits = SearchLimits {
+        playouts: move_time.is_none().then_some(num_playouts as u64),
+        move_time,
+        depth,
+        ..Default::default()
+    };
+    let threads = if use_parallel { 4 } else { 1 };
+    let mut engine: Box<dyn Engine> = match engine_name.as_deref() {
+        None | Some("mcts") => Box::new(MctsEngine::new(evaluator.clone(), SELFPLAY_EXPLORATION, threads, DEFAULT_TABLE_MB)),
+        Some(name) => create_engine(name, threads, DEFAULT_TABLE_MB)?,
+    };
+
+    if compare_prune {
+        compare_wall_filter(num_games, &limits, &evaluator, threads, max_turns);
+        return Ok(());
+    }
     let mut all_examples = Vec::new();
+    let mut rng = rand::thread_rng();
     
     for game_num in 1..=num_games {
-        let examples = play_self_play_game(game_num, num_playouts, &evaluator, use_parallel, log_metrics, "selfplay_metrics.csv", max_turns);
+        let opening = book.as_ref().map_or_else(Vec::new, |book| book.sample_line(book_depth, DEFAULT_MIN_GAMES, &mut rng));
+        let (examples, record) = play_self_play_game(game_num, &limits, engine.as_mut(), &opening, log_metrics, "selfplay_metrics.csv", max_turns);
         println!("  Generated {} training examples", examples.len());
         all_examples.extend(examples);
+        if let Some(path) = &records_path
+            && let Err(e) = append_record(path, &record) {
+            eprintln!("Failed to write {}: {}", path, e);
+        }
     }
     
     // Save to file
@@ -245,4 +383,5 @@ fn main() {
     println!("1. Run: cargo run --bin train");
     println!("2. Train network to predict MCTS policy (visit_counts) and outcome");
     println!("3. Iterate: better network → use in MCTS → generate better data");
+    Ok(())
 }
@@ -0,0 +1,31 @@
+use std::env;
+
+use quorridor::game_server::{Lobby, serve};
+
+fn print_usage() {
+    println!("Usage: server [--bind ADDR] [--records FILE]");
+    println!("  Hosts games for browsers at http://ADDR/ (default 127.0.0.1:8080).");
+    println!("  Binds to localhost only unless ADDR says otherwise, e.g. 0.0.0.0:8080 for the LAN.");
+    println!("  Finished games are appended to FILE as game records.");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|a| a == "--help") {
+        print_usage();
+        return;
+    }
+    let arg = |flag: &str| args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let bind = arg("--bind").unwrap_or_else(|| "127.0.0.1:8080".to_string());
+
+    match serve(&bind, Lobby::new(arg("--records"))) {
+        Ok(server) => {
+            println!("Serving games at http://{}/", server.addr);
+            server.join();
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use crate::quorridor::{Quorridor, shortest_path_to_goal};
+
+// Time left on one side's clock and what it gains after every move
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClockState {
+    pub remaining: Duration,
+    pub increment: Duration,
+}
+
+// A chess-style game clock with a Fischer increment for both players
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    remaining: [Duration; 2],
+    increment: Duration,
+}
+
+impl GameClock {
+    pub fn new(total: Duration, increment: Duration) -> Self {
+        GameClock { remaining: [total, total], increment }
+    }
+
+    pub fn state(&self, player: usize) -> ClockState {
+        ClockState { remaining: self.remaining[player], increment: self.increment }
+    }
+
+    pub fn remaining(&self, player: usize) -> Duration {
+        self.remaining[player]
+    }
+
+    // Charge a finished move to `player` and add the increment
+    pub fn record_move(&mut self, player: usize, elapsed: Duration) {
+        self.remaining[player] = self.remaining[player].saturating_sub(elapsed);
+        if !self.flagged(player) {
+            self.remaining[player] += self.increment;
+        }
+    }
+
+    pub fn flagged(&self, player: usize) -> bool {
+        self.remaining[player].is_zero()
+    }
+}
+
+// What a single search is allowed to spend. Unset limits are ignored; with no
+// limits at all the search falls back to DEFAULT_PLAYOUTS.
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub playouts: Option<u64>,
+    pub move_time: Option<Duration>,
+    pub clock: Option<ClockState>,
+    pub depth: Option<u32>,  // Alpha-beta only; MCTS ignores it
+}
+
+pub const DEFAULT_PLAYOUTS: u64 = 1000;
+
+impl SearchLimits {
+    pub fn playouts(playouts: u64) -> Self {
+        SearchLimits { playouts: Some(playouts), ..Default::default() }
+    }
+
+    pub fn move_time(move_time: Duration) -> Self {
+        SearchLimits { move_time: Some(move_time), ..Default::default() }
+    }
+
+    pub fn clock(clock: ClockState) -> Self {
+        SearchLimits { clock: Some(clock), ..Default::default() }
+    }
+
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits { depth: Some(depth), ..Default::default() }
+    }
+
+    pub fn is_timed(&self) -> bool {
+        self.move_time.is_some() || self.clock.is_some()
+    }
+
+    // Wall-clock budget for a search from `state`, if the limits are timed
+    pub fn budget(&self, state: &Quorridor) -> Option<MoveBudget> {
+        let from_clock = self.clock.map(|clock| allocate_move_time(state, clock));
+        match (self.move_time, from_clock) {
+            (Some(t), Some(b)) => Some(MoveBudget { soft: t.min(b.soft), hard: t.min(b.hard) }),
+            (Some(t), None) => Some(MoveBudget { soft: t, hard: t }),
+            (None, b) => b,
+        }
+    }
+}
+
+// `soft` is the normal target; the search may run on towards `hard` while the
+// top two moves are still close
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MoveBudget {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+// Never plan for fewer moves than this, so the clock is not burnt in one search
+const MIN_MOVES_TO_GO: u32 = 10;
+// Keep this much of the clock in reserve for the rest of the game
+const HARD_LIMIT_FRACTION: f64 = 0.25;
+// Left unspent on every move to cover the time between searches, or half the clock if less
+const CLOCK_MARGIN: Duration = Duration::from_millis(50);
+
+// Split the remaining clock over the moves this player is still expected to make
+pub fn allocate_move_time(state: &Quorridor, clock: ClockState) -> MoveBudget {
+    let player = state.active_player;
+    let path = shortest_path_to_goal(state, player).unwrap_or(0) as u32;
+    let walls = state.walls_remaining[player] as u32;
+
+    // A race takes about `path` moves; each wall still in hand tends to cost one more
+    let moves_to_go = (path + walls / 2).max(MIN_MOVES_TO_GO);
+
+    // The increment only arrives after the move, so it can never extend a search past the clock
+    let usable = clock.remaining - CLOCK_MARGIN.min(clock.remaining / 2);
+    let hard = (clock.remaining.mul_f64(HARD_LIMIT_FRACTION) + clock.increment / 2).min(usable);
+    let soft = (clock.remaining / moves_to_go + clock.increment.mul_f64(0.8)).min(hard);
+    MoveBudget { soft, hard: (soft * 3).min(hard) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_adds_increment() {
+        let mut clock = GameClock::new(Duration::from_secs(60), Duration::from_secs(2));
+        clock.record_move(0, Duration::from_secs(5));
+        assert_eq!(clock.remaining(0), Duration::from_secs(57));
+        assert_eq!(clock.remaining(1), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_clock_flag_falls() {
+        let mut clock = GameClock::new(Duration::from_secs(1), Duration::from_secs(2));
+        clock.record_move(1, Duration::from_secs(3));
+        assert!(clock.flagged(1));
+    }
+
+    #[test]
+    fn test_allocation_stays_within_clock() {
+        let state = Quorridor::default();
+        let clock = ClockState { remaining: Duration::from_secs(60), increment: Duration::ZERO };
+        let budget = allocate_move_time(&state, clock);
+        assert!(budget.soft <= budget.hard);
+        assert!(budget.hard <= Duration::from_secs(15));
+        assert!(budget.soft >= Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_increment_does_not_overrun_an_almost_empty_clock() {
+        let state = Quorridor::default();
+        let clock = ClockState { remaining: Duration::from_millis(100), increment: Duration::from_secs(2) };
+        let budget = allocate_move_time(&state, clock);
+        assert!(budget.soft <= budget.hard);
+        assert_eq!(budget.hard, Duration::from_millis(50));
+
+        let clock = ClockState { remaining: Duration::from_millis(10), ..clock };
+        assert_eq!(allocate_move_time(&state, clock).hard, Duration::from_millis(5));
+    }
+}
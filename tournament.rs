@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc;
+use std::thread;
+
+use quorridor::{engine::Engine, engine_config::EngineConfig, game_record::{play_game, random_opening}, ratings::{RatingsDb, fit_ratings}, elo::CONFIDENCE_Z};
+
+struct Participant {
+    name: String,
+    config: EngineConfig,
+}
+
+// One game of the schedule; `first` moves first
+struct Job {
+    first: usize,
+    second: usize,
+    opening_seed: u64,
+}
+
+fn print_usage() {
+    println!("Usage: tournament [--dir DIR] [--db FILE] [--playouts N] [--baselines LIST|none] [--games-per-pair N]");
+    println!("                  [--placement-games N] [--placement-opponents N] [--parallel N] [--max-plies N]");
+    println!("                  [--random-opening PLIES] [--seed N] [--anchor NAME] [--full]");
+    println!("  Every *.safetensors in DIR plays as network:playouts=N, named net/<file name>.");
+    println!("  Players without games in the database are placed against the existing ladder instead of a full round-robin.");
+    println!("  --full tops every pair up to --games-per-pair, e.g. to finish an interrupted first run.");
+}
+
+// Prefix of checkpoint players, so a file named like a baseline keeps its own row in the database
+const CHECKPOINT_PREFIX: &str = "net/";
+
+// Checkpoints of DIR, sorted by file name
+fn checkpoint_participants(dir: &str, playouts: u64) -> Result<Vec<Participant>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir, e))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "safetensors"))
+        .collect();
+    paths.sort();
+
+    paths.iter().map(|path| {
+        let name = path.file_stem().and_then(|s| s.to_str()).ok_or_else(|| format!("bad file name {}", path.display()))?;
+        let net = path.to_str().ok_or_else(|| format!("bad path {}", path.display()))?;
+        let config = EngineConfig {
+            spec: format!("network:net={},playouts={}", net, playouts),
+            network: Some(net.to_string()),
+            playouts: Some(playouts),
+            ..EngineConfig::parse("network")?
+        };
+        Ok(Participant { name: format!("{}{}", CHECKPOINT_PREFIX, name), config })
+    }).collect()
+}
+
+// Every pair plays `games` games in total, counting those already in the database
+fn round_robin(players: &[usize], games: u32, played: &HashMap<(usize, usize), u32>) -> Vec<(usize, usize, u32)> {
+    let mut pairs = Vec::new();
+    for (n, &i) in players.iter().enumerate() {
+        for &j in &players[n + 1..] {
+            let done = played.get(&(i.min(j), i.max(j))).copied().unwrap_or(0);
+            if done < games {
+                pairs.push((i, j, games - done));
+            }
+        }
+    }
+    pairs
+}
+
+// `count` opponents spread evenly over a ladder sorted by rating
+fn spread_opponents(ladder: &[usize], count: usize) -> Vec<usize> {
+    if ladder.len() <= count {
+        return ladder.to_vec();
+    }
+    (0..count).map(|k| ladder[k * (ladder.len() - 1) / (count - 1).max(1)]).collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|a| a == "--help") {
+        print_usage();
+        return;
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let arg = |flag: &str| args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let number = |flag: &str, default: usize| -> Result<usize, String> {
+        arg(flag).map_or(Ok(default), |v| v.parse().map_err(|_| format!("{} takes a whole number, got '{}'", flag, v)))
+    };
+
+    let dir = arg("--dir").unwrap_or_else(|| "checkpoints".to_string());
+    let db_path = arg("--db").unwrap_or_else(|| "ratings.sqlite".to_string());
+    let playouts = number("--playouts", 400)? as u64;
+    let baselines = arg("--baselines").unwrap_or_else(|| "random,shortest,walls,oneply".to_string());
+    let games_per_pair = number("--games-per-pair", 10)? as u32;
+    let placement_games = number("--placement-games", 24)? as u32;
+    let placement_opponents = number("--placement-opponents", 4)?.max(1);
+    let parallel = number("--parallel", 4)?.max(1);
+    let max_plies = number("--max-plies", 200)?;
+    let opening_plies = number("--random-opening", 4)?;
+    let seed = number("--seed", 0)? as u64;
+    let anchor = arg("--anchor").unwrap_or_else(|| "random".to_string());
+    let full = args.iter().any(|a| a == "--full");
+
+    let mut participants = Vec::new();
+    if baselines != "none" {
+        for name in baselines.split(',') {
+            participants.push(Participant { name: name.to_string(), config: EngineConfig::parse(name)? });
+        }
+    }
+    match checkpoint_participants(&dir, playouts) {
+        Ok(checkpoints) => participants.extend(checkpoints),
+        Err(e) => eprintln!("No checkpoints loaded: {}", e),
+    }
+    for (i, participant) in participants.iter().enumerate() {
+        if participants[..i].iter().any(|p| p.name == participant.name) {
+            return Err(format!("{} is listed twice", participant.name));
+        }
+    }
+    if participants.len() < 2 {
+        return Err("Need at least two players".to_string());
+    }
+    for participant in &participants {
+        participant.config.build().map_err(|e| format!("{}: {}", participant.name, e))?;
+    }
+
+    let mut db = RatingsDb::open(&db_path).map_err(|e| format!("Failed to open {}: {}", db_path, e))?;
+    let mut established = Vec::new();
+    let mut newcomers = Vec::new();
+    for (i, participant) in participants.iter().enumerate() {
+        if let Ok(Some(spec)) = db.player_spec(&participant.name)
+            && spec != participant.config.spec {
+            println!("Note: {} was rated as '{}', now '{}'", participant.name, spec, participant.config.spec);
+        }
+        db.add_player(&participant.name, &participant.config.spec)
+            .map_err(|e| format!("Failed to add {} to {}: {}", participant.name, db_path, e))?;
+        let games = db.games_played(&participant.name)
+            .map_err(|e| format!("Failed to read games for {} from {}: {}", participant.name, db_path, e))?;
+        if games > 0 {
+            established.push(i);
+        } else {
+            newcomers.push(i);
+        }
+    }
+
+    let index: HashMap<&str, usize> = participants.iter().enumerate().map(|(i, p)| (p.name.as_str(), i)).collect();
+    let mut played = HashMap::new();
+    let previous = db.pair_results().map_err(|e| format!("Failed to read games from {}: {}", db_path, e))?;
+    // The anchor may be a player from an earlier run who is no longer in DIR
+    if !index.contains_key(anchor.as_str()) && !previous.iter().any(|r| r.a == anchor || r.b == anchor) {
+        return Err(format!("Anchor {} is not in the tournament or {}", anchor, db_path));
+    }
+    for result in previous {
+        if let (Some(&i), Some(&j)) = (index.get(result.a.as_str()), index.get(result.b.as_str())) {
+            played.insert((i.min(j), i.max(j)), result.games());
+        }
+    }
+
+    // A fresh ladder starts with a full round-robin; later runs only place the newcomers
+    let mut pairs = Vec::new();
+    if established.len() < 2 || full {
+        let everyone: Vec<usize> = (0..participants.len()).collect();
+        pairs.extend(round_robin(&everyone, games_per_pair, &played));
+    } else {
+        let stored = db.ratings().map_err(|e| format!("Failed to read ratings from {}: {}", db_path, e))?;
+        let mut ladder = established.clone();
+        ladder.sort_by(|&a, &b| {
+            let elo = |i: usize| stored.iter().find(|r| r.name == participants[i].name).map_or(0.0, |r| r.elo);
+            elo(b).total_cmp(&elo(a))
+        });
+        for &newcomer in &newcomers {
+            let opponents = spread_opponents(&ladder, placement_opponents);
+            let per_opponent = placement_games.div_ceil(opponents.len() as u32).next_multiple_of(2).max(2);
+            for opponent in opponents {
+                let done = played.get(&(newcomer.min(opponent), newcomer.max(opponent))).copied().unwrap_or(0);
+                if done < per_opponent {
+                    pairs.push((newcomer, opponent, per_opponent - done));
+                }
+            }
+        }
+    }
+
+    // Colors alternate within a pair, and each opening is played from both sides
+    let mut jobs = Vec::new();
+    for (i, j, games) in pairs {
+        for k in 0..games as u64 {
+            let (first, second) = if k % 2 == 0 { (i, j) } else { (j, i) };
+            let opening_seed = seed.wrapping_add(((i as u64) << 40) ^ ((j as u64) << 20) ^ (k / 2));
+            jobs.push(Job { first, second, opening_seed });
+        }
+    }
+
+    println!("=== Quorridor Tournament ===");
+    println!("  {} players ({} established, {} new), {} games to play, {} in parallel", participants.len(), established.len(), newcomers.len(), jobs.len(), parallel);
+    println!("  Ratings database: {}", db_path);
+    println!();
+
+    let total = jobs.len();
+    let participants = Arc::new(participants);
+    let jobs = Arc::new(Mutex::new(jobs.into_iter().enumerate().collect::<Vec<_>>().into_iter()));
+    let (tx, rx) = mpsc::channel();
+
+    // Engines are not Send, so every worker builds its own, on first use
+    let workers: Vec<_> = (0..parallel).map(|_| {
+        let (participants, jobs, tx) = (participants.clone(), jobs.clone(), tx.clone());
+        thread::spawn(move || {
+            let mut engines: HashMap<usize, Box<dyn Engine>> = HashMap::new();
+            loop {
+                let Some((number, job)) = jobs.lock().unwrap().next() else { break };
+                for i in [job.first, job.second] {
+                    engines.entry(i).or_insert_with(|| participants[i].config.build().unwrap());
+                }
+                let [first, second] = engines.get_disjoint_mut([&job.first, &job.second]).map(Option::unwrap);
+                let limits = [participants[job.first].config.limits(), participants[job.second].config.limits()];
+                let opening = random_opening(job.opening_seed, opening_plies);
+                let mut record = play_game([first.as_mut(), second.as_mut()], [&limits[0], &limits[1]], &opening, max_plies, None);
+                record.players = [participants[job.first].name.clone(), participants[job.second].name.clone()];
+                if tx.send((number, record)).is_err() {
+                    break;
+                }
+            }
+        })
+    }).collect();
+    drop(tx);
+
+    for (completed, (number, record)) in rx.into_iter().enumerate() {
+        let outcome = match record.winner {
+            Some(w) => format!("{} wins", record.players[w]),
+            None => "draw".to_string(),
+        };
+        println!("[{:>4}/{}] game {:>4}: {} vs {}: {} in {} plies",
+            completed + 1, total, number + 1, record.players[0], record.players[1], outcome, record.moves.len());
+        // Returning drops the receiver, which stops the workers after their current game
+        db.add_game(&record).map_err(|e| format!("Failed to store game in {}: {}", db_path, e))?;
+    }
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    // Ratings are refit over every game in the database, including players no longer in DIR
+    let results = db.pair_results().map_err(|e| format!("Failed to read games from {}: {}", db_path, e))?;
+    let mut ratings = fit_ratings(&results, Some(&anchor));
+    ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+    db.store_ratings(&ratings).map_err(|e| format!("Failed to store ratings in {}: {}", db_path, e))?;
+
+    println!();
+    if ratings.iter().any(|r| r.name == anchor) {
+        println!("=== Ladder (anchored at {} = 0) ===", anchor);
+    } else {
+        println!("=== Ladder (anchor {} has no games, mean = 0) ===", anchor);
+    }
+    println!("{:>4}  {:<24} {:>8} {:>7} {:>6}", "Rank", "Player", "Elo", "+/-", "Games");
+    for (rank, rating) in ratings.iter().enumerate() {
+        println!("{:>4}  {:<24} {:>8.0} {:>7.0} {:>6}", rank + 1, rating.name, rating.elo, CONFIDENCE_Z * rating.stderr, rating.games);
+    }
+    Ok(())
+}
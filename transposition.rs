@@ -0,0 +1,317 @@
+use mcts::*;
+use mcts::transposition_table::*;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::mcts_impl::MyMCTS;
+use crate::quorridor::{Quorridor, GRID_WIDTH};
+
+pub const DEFAULT_TABLE_MB: usize = 64;
+const BUCKET_SLOTS: usize = 4;
+
+// What to do when every slot of a position's bucket is taken
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum ReplacementPolicy {
+    // Keep what is there. Earlier entries sit closer to the root and carry more
+    // visits, so this is the default for a single search.
+    KeepOldest,
+    // Evict the entry stored longest ago, favouring the part of the tree being searched now
+    ReplaceOldest,
+    // Evict a slot chosen by the new key
+    AlwaysReplace,
+}
+
+#[derive(Copy, Clone, Default)]
+struct Slot {
+    key: u64,           // Zobrist hash; 0 marks an empty slot
+    check: u32,         // Fingerprint of the position, to catch two positions sharing a key
+    node: usize,        // Address of the SearchNode in the tree that owns this table
+    stored_at: u64,     // Store sequence number, for age-based replacement
+}
+
+#[derive(Default)]
+pub struct TableStats {
+    lookups: AtomicU64,
+    hits: AtomicU64,
+    stores: AtomicU64,
+    replacements: AtomicU64,
+    rejected: AtomicU64,
+    collisions: AtomicU64,
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize)]
+pub struct TableStatsSnapshot {
+    pub lookups: u64,
+    pub hits: u64,
+    pub stores: u64,
+    pub replacements: u64,  // Stores that evicted another position
+    pub rejected: u64,      // Stores dropped because the bucket was full
+    // Lookups and stores that found their key held by another position. Only
+    // collisions between positions that differ in pawns, walls in hand or side
+    // to move are caught; they are treated as misses rather than transpositions.
+    pub collisions: u64,
+    pub capacity: usize,
+}
+
+impl TableStatsSnapshot {
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups == 0 { 0.0 } else { self.hits as f64 / self.lookups as f64 }
+    }
+
+    // Share of stores that found their bucket full, whether they evicted an
+    // entry or were dropped. Distinct keys sharing a bucket is expected and not
+    // counted here.
+    pub fn eviction_rate(&self) -> f64 {
+        let attempts = self.stores + self.rejected;
+        if attempts == 0 { 0.0 } else { (self.replacements + self.rejected) as f64 / attempts as f64 }
+    }
+}
+
+// Transposition table for MyMCTS sized from a memory budget. Positions map to
+// buckets of BUCKET_SLOTS entries; a full bucket is resolved by the
+// ReplacementPolicy. Entries point into the search tree, which owns the table,
+// so an evicted entry only loses the shortcut, never the node itself.
+pub struct QuorridorTable {
+    buckets: Box<[Mutex<[Slot; BUCKET_SLOTS]>]>,
+    policy: ReplacementPolicy,
+    sequence: AtomicU64,
+    stats: Arc<TableStats>,
+}
+
+// Slots per node a search may create, so that buckets rarely fill up
+const SLOTS_PER_NODE: u64 = 2;
+
+// Second fingerprint checked alongside the zobrist key: pawn squares, walls in
+// hand and side to move, packed exactly
+fn position_check(state: &Quorridor) -> u32 {
+    let [p0, p1] = &state.player_pieces;
+    let squares = [p0.x, p0.y, p1.x, p1.y].iter().fold(0, |acc, &c| acc * GRID_WIDTH as u64 + c as u64);
+    let walls = state.walls_remaining[0] as u64 * 11 + state.walls_remaining[1] as u64;
+    ((squares * 121 + walls) * 2 + state.active_player as u64) as u32
+}
+
+fn bucket_size() -> usize {
+    std::mem::size_of::<Mutex<[Slot; BUCKET_SLOTS]>>()
+}
+
+impl QuorridorTable {
+    pub fn with_memory_mb(megabytes: usize, policy: ReplacementPolicy) -> Self {
+        QuorridorTable::with_buckets(megabytes * 1024 * 1024 / bucket_size(), policy)
+    }
+
+    // Room for a search that creates at most `nodes` nodes, and no more than
+    // `megabytes`. Short searches then skip allocating and clearing the full budget.
+    pub fn for_nodes(nodes: u64, megabytes: usize, policy: ReplacementPolicy) -> Self {
+        let needed = nodes.saturating_mul(SLOTS_PER_NODE).div_ceil(BUCKET_SLOTS as u64);
+        let budget = megabytes * 1024 * 1024 / bucket_size();
+        QuorridorTable::with_buckets(needed.min(budget as u64) as usize, policy)
+    }
+
+    fn with_buckets(num_buckets: usize, policy: ReplacementPolicy) -> Self {
+        let num_buckets = num_buckets.max(1);
+        QuorridorTable {
+            buckets: (0..num_buckets).map(|_| Mutex::new([Slot::default(); BUCKET_SLOTS])).collect(),
+            policy,
+            sequence: AtomicU64::new(0),
+            stats: Arc::new(TableStats::default()),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SLOTS
+    }
+
+    // Nodes a search may create before a table of `megabytes` holds fewer than
+    // SLOTS_PER_NODE slots for each
+    pub fn node_capacity_for_mb(megabytes: usize) -> usize {
+        (megabytes * 1024 * 1024 / bucket_size()).max(1) * BUCKET_SLOTS / SLOTS_PER_NODE as usize
+    }
+
+    // Counters shared with the table; keep a handle before moving the table into a search
+    pub fn stats(&self) -> TableStatsHandle {
+        TableStatsHandle { stats: self.stats.clone(), capacity: self.capacity() }
+    }
+
+    fn bucket(&self, key: u64) -> &Mutex<[Slot; BUCKET_SLOTS]> {
+        &self.buckets[(key % self.buckets.len() as u64) as usize]
+    }
+
+    // Store `node` under `hash` unless the position is already there, in which
+    // case the stored node is returned instead
+    fn store(&self, hash: u64, check: u32, node: usize) -> Option<usize> {
+        let mut bucket = self.bucket(hash).lock().unwrap();
+
+        if let Some(slot) = bucket.iter().find(|slot| slot.key == hash) {
+            if slot.check != check {
+                // Another position holds the key; leave it and search this one without a shortcut
+                self.stats.collisions.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            return Some(slot.node);
+        }
+
+        let index = match bucket.iter().position(|slot| slot.key == 0) {
+            Some(empty) => empty,
+            None => {
+                let victim = match self.policy {
+                    ReplacementPolicy::KeepOldest => {
+                        self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+                        return None;
+                    }
+                    ReplacementPolicy::ReplaceOldest => (0..BUCKET_SLOTS).min_by_key(|&i| bucket[i].stored_at).unwrap(),
+                    ReplacementPolicy::AlwaysReplace => (hash >> 32) as usize % BUCKET_SLOTS,
+                };
+                self.stats.replacements.fetch_add(1, Ordering::Relaxed);
+                victim
+            }
+        };
+
+        bucket[index] = Slot { key: hash, check, node, stored_at: self.sequence.fetch_add(1, Ordering::Relaxed) };
+        self.stats.stores.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    fn find(&self, hash: u64, check: u32) -> Option<usize> {
+        self.stats.lookups.fetch_add(1, Ordering::Relaxed);
+        let bucket = self.bucket(hash).lock().unwrap();
+        let slot = bucket.iter().find(|slot| slot.key == hash)?;
+        if slot.check != check {
+            self.stats.collisions.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.stats.hits.fetch_add(1, Ordering::Relaxed);
+        Some(slot.node)
+    }
+}
+
+impl Default for QuorridorTable {
+    fn default() -> Self {
+        QuorridorTable::with_memory_mb(DEFAULT_TABLE_MB, ReplacementPolicy::KeepOldest)
+    }
+}
+
+#[derive(Clone)]
+pub struct TableStatsHandle {
+    stats: Arc<TableStats>,
+    capacity: usize,
+}
+
+impl TableStatsHandle {
+    pub fn snapshot(&self) -> TableStatsSnapshot {
+        TableStatsSnapshot {
+            lookups: self.stats.lookups.load(Ordering::Relaxed),
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            stores: self.stats.stores.load(Ordering::Relaxed),
+            replacements: self.stats.replacements.load(Ordering::Relaxed),
+            rejected: self.stats.rejected.load(Ordering::Relaxed),
+            collisions: self.stats.collisions.load(Ordering::Relaxed),
+            capacity: self.capacity,
+        }
+    }
+}
+
+// Safety: every stored address is a node of the tree that owns this table, and
+// lookups only return addresses stored under the same full 64-bit key and check
+unsafe impl TranspositionTable<MyMCTS> for QuorridorTable {
+    fn insert<'a>(&'a self, key: &Quorridor, value: &'a SearchNode<MyMCTS>,
+                  _: SearchHandle<MyMCTS>) -> Option<&'a SearchNode<MyMCTS>> {
+        let existing = self.store(key.hash(), position_check(key), value as *const SearchNode<MyMCTS> as usize)?;
+        Some(unsafe { &*(existing as *const SearchNode<MyMCTS>) })
+    }
+
+    fn lookup<'a>(&'a self, key: &Quorridor, _: SearchHandle<MyMCTS>) -> Option<&'a SearchNode<MyMCTS>> {
+        let node = self.find(key.hash(), position_check(key))?;
+        Some(unsafe { &*(node as *const SearchNode<MyMCTS>) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A one-bucket table, so every key lands in the same bucket
+    fn single_bucket(policy: ReplacementPolicy) -> QuorridorTable {
+        let table = QuorridorTable::with_memory_mb(0, policy);
+        assert_eq!(table.capacity(), BUCKET_SLOTS);
+        table
+    }
+
+    // Keys with distinct high halves, so AlwaysReplace picks a known slot
+    fn key(i: u64) -> u64 {
+        (i << 32) | i
+    }
+
+    #[test]
+    fn test_store_and_find() {
+        let table = single_bucket(ReplacementPolicy::KeepOldest);
+        assert_eq!(table.find(key(1), 0), None);
+        assert_eq!(table.store(key(1), 0, 100), None);
+        assert_eq!(table.find(key(1), 0), Some(100));
+        // A second store of the same position hands back the first node
+        assert_eq!(table.store(key(1), 0, 200), Some(100));
+        assert_eq!(table.find(key(2), 0), None);
+
+        let stats = table.stats().snapshot();
+        assert_eq!((stats.lookups, stats.hits, stats.stores), (3, 1, 1));
+        assert_eq!(stats.hit_rate(), 1.0 / 3.0);
+        assert_eq!(stats.eviction_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_full_bucket_policies() {
+        for policy in [ReplacementPolicy::KeepOldest, ReplacementPolicy::ReplaceOldest, ReplacementPolicy::AlwaysReplace] {
+            let table = single_bucket(policy);
+            for i in 1..=BUCKET_SLOTS as u64 {
+                assert_eq!(table.store(key(i), 0, i as usize), None);
+            }
+            let newcomer = key(BUCKET_SLOTS as u64 + 2);
+            assert_eq!(table.store(newcomer, 0, 99), None);
+
+            let kept: Vec<bool> = (1..=BUCKET_SLOTS as u64).map(|i| table.find(key(i), 0).is_some()).collect();
+            let stats = table.stats().snapshot();
+            match policy {
+                ReplacementPolicy::KeepOldest => {
+                    assert_eq!(table.find(newcomer, 0), None);
+                    assert!(kept.iter().all(|&k| k));
+                    assert_eq!((stats.rejected, stats.replacements), (1, 0));
+                }
+                ReplacementPolicy::ReplaceOldest => {
+                    assert_eq!(table.find(newcomer, 0), Some(99));
+                    assert_eq!(kept, [false, true, true, true]);
+                    assert_eq!((stats.rejected, stats.replacements), (0, 1));
+                }
+                ReplacementPolicy::AlwaysReplace => {
+                    // The high half of the newcomer's key picks slot 2, holding key 3
+                    assert_eq!(table.find(newcomer, 0), Some(99));
+                    assert_eq!(kept, [true, true, false, true]);
+                    assert_eq!((stats.rejected, stats.replacements), (0, 1));
+                }
+            }
+            let attempts = stats.stores + stats.rejected;
+            assert_eq!(stats.eviction_rate(), 1.0 / attempts as f64);
+        }
+    }
+
+    #[test]
+    fn test_collisions_and_sizing() {
+        let table = single_bucket(ReplacementPolicy::KeepOldest);
+        assert_eq!(table.store(key(1), 7, 100), None);
+        // Same key, different position: neither a hit nor a replacement
+        assert_eq!(table.find(key(1), 8), None);
+        assert_eq!(table.store(key(1), 8, 200), None);
+        assert_eq!(table.find(key(1), 7), Some(100));
+        let stats = table.stats().snapshot();
+        assert_eq!((stats.hits, stats.stores, stats.collisions), (1, 1, 2));
+
+        let mut moved = Quorridor::default();
+        moved.make_move(&crate::moves::Move::Down);
+        assert_ne!(position_check(&Quorridor::default()), position_check(&moved));
+
+        let small = QuorridorTable::for_nodes(1000, DEFAULT_TABLE_MB, ReplacementPolicy::KeepOldest);
+        assert_eq!(small.capacity(), 2000);
+        let capped = QuorridorTable::for_nodes(u64::MAX, 1, ReplacementPolicy::KeepOldest);
+        assert_eq!(capped.capacity(), QuorridorTable::with_memory_mb(1, ReplacementPolicy::KeepOldest).capacity());
+    }
+}
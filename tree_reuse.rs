@@ -0,0 +1,159 @@
+use mcts::*;
+use mcts::transposition_table::TranspositionHash;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use crate::mcts_impl::{MyMCTS, MovePrior};
+use crate::moves::Move;
+use crate::quorridor::Quorridor;
+
+// Upper bound on how many nodes of the old tree are carried into the next search
+pub const DEFAULT_REUSE_NODES: usize = 50_000;
+
+// Edge statistics kept from a previous search, keyed by the hash of the position
+// each edge leads to. The mcts crate cannot re-root a tree, so the next search
+// starts from a fresh tree and seeds every edge it expands with these visits.
+#[derive(Default)]
+pub struct InheritedTree {
+    expanded: HashSet<u64>,
+    edges: HashMap<u64, MovePrior>,
+}
+
+impl InheritedTree {
+    // Collect the subtree reached by playing `played` from the root of `mcts`
+    pub fn from_subtree(mcts: &MCTSManager<MyMCTS>, played: &[Move], max_nodes: usize) -> Self {
+        let mut inherited = InheritedTree::default();
+
+        let mut node = mcts.tree().root_node();
+        let mut state = mcts.tree().root_state().clone();
+        for mov in played {
+            let child = node.moves().find(|m| m.get_move() == mov).and_then(|m| m.child());
+            match child {
+                Some(child) => node = child,
+                None => return inherited,
+            }
+            state.make_move(mov);
+        }
+
+        let mut queue = VecDeque::new();
+        queue.push_back((node, state));
+
+        while let Some((node, state)) = queue.pop_front() {
+            if inherited.expanded.len() >= max_nodes || !inherited.expanded.insert(state.hash()) {
+                continue;
+            }
+
+            for info in node.moves() {
+                let prior = info.move_evaluation();
+                let visits = info.visits() + prior.visits;
+                if visits == 0 {
+                    continue;
+                }
+
+                let mut child_state = state.clone();
+                child_state.make_move(info.get_move());
+                inherited.edges.insert(child_state.hash(), MovePrior {
+                    visits,
+                    sum_rewards: info.sum_rewards() + prior.sum_rewards,
+                    pruned: false,
+                    proven: None,
+                });
+
+                if let Some(child) = info.child() {
+                    queue.push_back((child, child_state));
+                }
+            }
+        }
+
+        inherited
+    }
+
+    // Whether the previous search expanded this position
+    pub fn contains(&self, state: &Quorridor) -> bool {
+        self.expanded.contains(&state.hash())
+    }
+
+    pub fn prior(&self, child_state: &Quorridor) -> MovePrior {
+        self.edges.get(&child_state.hash()).copied().unwrap_or_default()
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.expanded.len()
+    }
+}
+
+// Carries the search tree from one move to the next within a single game
+pub struct TreeReuse {
+    inherited: Option<Arc<InheritedTree>>,
+    max_nodes: usize,
+}
+
+impl TreeReuse {
+    pub fn new() -> Self {
+        TreeReuse { inherited: None, max_nodes: DEFAULT_REUSE_NODES }
+    }
+
+    pub fn with_max_nodes(max_nodes: usize) -> Self {
+        TreeReuse { inherited: None, max_nodes }
+    }
+
+    // Inherited statistics to hand to the evaluator of the next search
+    pub fn inherited(&self) -> Option<Arc<InheritedTree>> {
+        self.inherited.clone()
+    }
+
+    // Keep the part of the finished search that lies under the moves just played
+    pub fn advance(&mut self, mcts: &MCTSManager<MyMCTS>, played: &[Move]) {
+        let subtree = InheritedTree::from_subtree(mcts, played, self.max_nodes);
+        self.inherited = if subtree.num_nodes() > 0 { Some(Arc::new(subtree)) } else { None };
+    }
+
+    pub fn clear(&mut self) {
+        self.inherited = None;
+    }
+}
+
+impl Default for TreeReuse {
+    fn default() -> Self {
+        TreeReuse::new()
+    }
+}
+
+// Visits the root of `mcts` started with rather than earned in this search
+pub fn inherited_visits(mcts: &MCTSManager<MyMCTS>) -> u64 {
+    mcts.tree().root_node().moves().map(|m| m.move_evaluation().visits).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts_impl::MyEvaluator;
+
+    #[test]
+    fn test_inherited_priors_are_keyed_by_child_position() {
+        let root = Quorridor::default();
+        let mut child = root.clone();
+        child.make_move(&Move::Down);
+        let mut tree = InheritedTree::default();
+        tree.expanded.insert(root.hash());
+        tree.edges.insert(child.hash(), MovePrior { visits: 40, sum_rewards: 12000, pruned: false, proven: None });
+        assert!(tree.contains(&root));
+        assert!(!tree.contains(&child));
+
+        let evaluator = MyEvaluator::new().with_inherited(Some(Arc::new(tree)));
+        let moves = root.available_moves();
+        let (priors, _) = evaluator.evaluate_new_state(&root, &moves, None);
+        for (mov, prior) in moves.iter().zip(&priors) {
+            let expected = if *mov == Move::Down { 40 } else { 0 };
+            assert_eq!(prior.visits, expected, "{:?}", mov);
+        }
+        assert_eq!(priors.iter().map(|p| p.sum_rewards).sum::<i64>(), 12000);
+
+        // Positions the old search never expanded start without priors
+        let mut other = root.clone();
+        other.make_move(&Move::Left);
+        let moves = other.available_moves();
+        let (priors, _) = evaluator.evaluate_new_state(&other, &moves, None);
+        assert!(priors.iter().all(|p| p.visits == 0));
+    }
+}
@@ -0,0 +1,439 @@
+// Full-screen terminal front-end: a colored board drawn from the grid size,
+// legal pawn destinations highlighted, a wall cursor driven by the arrow keys,
+// and a side panel with path lengths, walls, the move list and search stats.
+use crossterm::{cursor, execute, queue, terminal};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+use crate::moves::Move;
+use crate::path_analysis::{PathOverlay, path_overlay, wall_impacts};
+use crate::quorridor::{Quorridor, GRID_HEIGHT, GRID_WIDTH, move_delta, shortest_path_to_goal};
+use crate::search_result::SearchResult;
+use crate::wall::{Orientation, Wall};
+
+// Messages kept at the bottom of the panel
+const MAX_MESSAGES: usize = 4;
+// Columns between the board and the panel
+const PANEL_GAP: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Style {
+    Frame,
+    Label,
+    Square,
+    Wall,
+    Pawn(usize),
+    Destination,
+    Path(usize),  // On the shortest route of player 0, 1, or 2 for both
+    Critical,     // An edge on every shortest route of a player
+    Selected,
+    WallCursor,
+    BadWallCursor,  // The wall under the cursor cannot be placed
+    Heading,
+    Text,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+pub type Line = Vec<Span>;
+
+fn span(text: impl Into<String>, style: Style) -> Span {
+    Span { text: text.into(), style }
+}
+
+// What the arrow keys move: a square for pawn moves or a wall anchor
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Selection {
+    Square(i64, i64),
+    Wall(i64, i64, Orientation),
+}
+
+// Legal pawn moves by destination square
+pub fn pawn_destinations(game: &Quorridor) -> Vec<((i64, i64), Move)> {
+    let piece = game.player_pieces[game.active_player];
+    game.legal_moves().into_iter()
+        .filter_map(|mov| move_delta(&mov).map(|(dx, dy)| ((piece.x + dx, piece.y + dy), mov)))
+        .collect()
+}
+
+// The board as styled lines, highest row first so player 0 moves up the
+// screen. Odd grid columns are squares and horizontal edges, three characters
+// wide; even columns are corners and vertical edges, one character wide.
+// `overlay` adds both shortest routes and their critical edges.
+pub fn render_board(game: &Quorridor, selection: Option<Selection>, overlay: Option<&PathOverlay>) -> Vec<Line> {
+    let destinations: Vec<(i64, i64)> = pawn_destinations(game).into_iter().map(|(square, _)| square).collect();
+    let (cursor_cells, cursor_style) = match selection {
+        Some(Selection::Wall(x, y, orientation)) => {
+            let legal = game.legal_moves().contains(&Move::PlaceWall(x, y, orientation));
+            (Wall { x, y, orientation }.positions().to_vec(), if legal { Style::WallCursor } else { Style::BadWallCursor })
+        }
+        _ => (Vec::new(), Style::WallCursor),
+    };
+    let wall_at = |x: i64, y: i64| {
+        (0..GRID_WIDTH as i64).contains(&x) && (0..GRID_HEIGHT as i64).contains(&y) && game.grid[y as usize][x as usize]
+    };
+    let on_route = |x: i64, y: i64| {
+        let players: Vec<usize> = (0..2).filter(|&p| overlay.is_some_and(|o| o.routes[p].contains(&(x, y)))).collect();
+        match players.as_slice() {
+            [] => None,
+            [player] => Some(*player),
+            _ => Some(2),
+        }
+    };
+    let critical = |x: i64, y: i64| overlay.is_some_and(|o| o.critical.iter().any(|edges| edges.contains(&(x, y))));
+    let edge_style = |x: i64, y: i64| if wall_at(x, y) { Style::Wall } else if critical(x, y) { Style::Critical } else { Style::Frame };
+
+    let mut lines = Vec::new();
+    for y in (0..GRID_HEIGHT as i64).rev() {
+        let label = if y % 2 == 1 { format!("{:>2} ", (y - 1) / 2 + 1) } else { "   ".to_string() };
+        let mut line = vec![span(label, Style::Label)];
+        for x in 0..GRID_WIDTH as i64 {
+            let cell = match (x % 2 == 1, y % 2 == 1) {
+                (true, true) => {
+                    let pawn = game.player_pieces.iter().position(|piece| (piece.x, piece.y) == (x, y));
+                    let text = match pawn {
+                        Some(player) => format!(" {} ", player),
+                        None if destinations.contains(&(x, y)) => " · ".to_string(),
+                        None if on_route(x, y).is_some() => " ∙ ".to_string(),
+                        None => "   ".to_string(),
+                    };
+                    let style = if selection == Some(Selection::Square(x, y)) {
+                        Style::Selected
+                    } else if let Some(player) = pawn {
+                        Style::Pawn(player)
+                    } else if destinations.contains(&(x, y)) {
+                        Style::Destination
+                    } else if let Some(route) = on_route(x, y) {
+                        Style::Path(route)
+                    } else {
+                        Style::Square
+                    };
+                    span(text, style)
+                }
+                (true, false) => span(if wall_at(x, y) { "━━━" } else { "───" }, edge_style(x, y)),
+                (false, true) => span(if wall_at(x, y) { "┃" } else { "│" }, edge_style(x, y)),
+                (false, false) => {
+                    // The middle of a wall sits on a corner; draw it along the wall
+                    let glyph = if !wall_at(x, y) { "┼" } else if wall_at(x - 1, y) || wall_at(x + 1, y) { "━" } else { "┃" };
+                    span(glyph, if wall_at(x, y) { Style::Wall } else { Style::Frame })
+                }
+            };
+            let cell = if cursor_cells.contains(&(x, y)) {
+                let glyph = match (x % 2 == 1, y % 2 == 1, selection) {
+                    (true, _, _) => "━━━",
+                    (false, true, _) => "┃",
+                    (false, false, Some(Selection::Wall(_, _, Orientation::Horizontal))) => "━",
+                    (false, false, _) => "┃",
+                };
+                span(glyph, cursor_style)
+            } else {
+                cell
+            };
+            line.push(cell);
+        }
+        lines.push(line);
+    }
+
+    let mut columns = String::from("   ");
+    for x in 0..GRID_WIDTH as i64 {
+        columns += &if x % 2 == 1 { format!(" {} ", (b'a' + (x / 2) as u8) as char) } else { " ".to_string() };
+    }
+    lines.push(vec![span(columns, Style::Label)]);
+    lines
+}
+
+// What the side panel shows besides the position itself
+#[derive(Clone, Debug, Default)]
+pub struct PanelInfo {
+    pub names: [String; 2],
+    pub moves: Vec<String>,
+    pub search: Option<SearchResult>,  // The last AI search
+    pub messages: Vec<String>,
+    pub help: Vec<String>,
+    pub show_paths: bool,  // Draw the path overlay and list the walls that change distances
+}
+
+// Walls listed in the panel with their effect on both distances
+const MAX_WALL_IMPACTS: usize = 5;
+
+pub fn render_panel(game: &Quorridor, info: &PanelInfo) -> Vec<Line> {
+    let mut lines = Vec::new();
+    for player in (0..2).rev() {
+        let path = shortest_path_to_goal(game, player).map_or("-".to_string(), |steps| steps.to_string());
+        let marker = if game.active_player == player && !game.game_over() { "▶" } else { " " };
+        lines.push(vec![
+            span(format!("{} ", marker), Style::Text),
+            span(format!(" {} ", player), Style::Pawn(player)),
+            span(format!(" {:<12} path {:>2}  walls {:>2}", info.names[player], path, game.walls_remaining[player]), Style::Text),
+        ]);
+    }
+    lines.push(Vec::new());
+
+    lines.push(vec![span("Moves", Style::Heading)]);
+    // The last few move pairs, numbered
+    let pairs: Vec<String> = info.moves.chunks(2).enumerate()
+        .map(|(i, pair)| format!("{:>3}. {:<5} {}", i + 1, pair[0], pair.get(1).map_or("", String::as_str)))
+        .collect();
+    for pair in &pairs[pairs.len().saturating_sub(6)..] {
+        lines.push(vec![span(pair.clone(), Style::Text)]);
+    }
+    lines.push(Vec::new());
+
+    if let Some(result) = &info.search {
+        lines.push(vec![span("Search", Style::Heading)]);
+        lines.push(vec![span(format!("best {}  win {:.1}%", result.best_move_notation.as_deref().unwrap_or("-"), result.win_probability * 100.0), Style::Text)]);
+        let work = match result.depth {
+            Some(depth) => format!("depth {}, {} nodes", depth, result.playouts),
+            None => format!("{} playouts", result.playouts),
+        };
+        lines.push(vec![span(format!("{} in {:.2}s, {:.0}/s", work, result.elapsed_ms / 1000.0, result.playouts_per_second), Style::Text)]);
+        if let Some(proven) = result.proven {
+            lines.push(vec![span(format!("proven: {}", proven), Style::Text)]);
+        }
+        lines.push(vec![span(format!("pv {}", result.principal_variation_notation.iter().take(8).cloned().collect::<Vec<_>>().join(" ")), Style::Text)]);
+        lines.push(Vec::new());
+    }
+
+    if info.show_paths && !game.game_over() {
+        lines.push(vec![span("Walls", Style::Heading)]);
+        for impact in wall_impacts(game).iter().take(MAX_WALL_IMPACTS) {
+            lines.push(vec![span(format!("{:<5} P0 {:+}  P1 {:+}", impact.notation, impact.delta[0], impact.delta[1]), Style::Text)]);
+        }
+        lines.push(Vec::new());
+    }
+
+    for message in &info.messages[info.messages.len().saturating_sub(MAX_MESSAGES)..] {
+        lines.push(vec![span(message.clone(), Style::Text)]);
+    }
+    lines.push(Vec::new());
+    for help in &info.help {
+        lines.push(vec![span(help.clone(), Style::Label)]);
+    }
+    lines
+}
+
+fn colors(style: Style) -> (Color, Option<Color>) {
+    match style {
+        Style::Frame => (Color::DarkGrey, None),
+        Style::Label => (Color::Grey, None),
+        Style::Square => (Color::Reset, None),
+        Style::Wall => (Color::Yellow, None),
+        Style::Pawn(0) => (Color::Black, Some(Color::Cyan)),
+        Style::Pawn(_) => (Color::Black, Some(Color::Magenta)),
+        Style::Destination => (Color::Green, None),
+        Style::Path(0) => (Color::Cyan, None),
+        Style::Path(1) => (Color::Magenta, None),
+        Style::Path(_) => (Color::Blue, None),
+        Style::Critical => (Color::Red, None),
+        Style::Selected => (Color::Black, Some(Color::Green)),
+        Style::WallCursor => (Color::Green, None),
+        Style::BadWallCursor => (Color::Red, None),
+        Style::Heading => (Color::White, None),
+        Style::Text => (Color::Reset, None),
+    }
+}
+
+fn line_width(line: &Line) -> usize {
+    line.iter().map(|span| span.text.chars().count()).sum()
+}
+
+// The terminal in raw mode on the alternate screen; restored when dropped
+pub struct Tui {
+    out: Stdout,
+    pub info: PanelInfo,
+    selection: Selection,
+}
+
+impl Tui {
+    pub fn enter(names: [String; 2]) -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Tui { out, info: PanelInfo { names, ..Default::default() }, selection: Selection::Square(1, 1) })
+    }
+
+    pub fn message(&mut self, text: impl Into<String>) {
+        self.info.messages.push(text.into());
+    }
+
+    pub fn draw(&mut self, game: &Quorridor, selection: Option<Selection>) -> io::Result<()> {
+        let overlay = self.info.show_paths.then(|| path_overlay(game));
+        let board = render_board(game, selection, overlay.as_ref());
+        let panel = render_panel(game, &self.info);
+        let board_width = board.iter().map(line_width).max().unwrap_or(0);
+
+        queue!(self.out, terminal::Clear(terminal::ClearType::All))?;
+        for (row, line) in board.iter().enumerate() {
+            self.print_line(0, row, line)?;
+        }
+        for (row, line) in panel.iter().enumerate() {
+            self.print_line(board_width + PANEL_GAP, row, line)?;
+        }
+        self.out.flush()
+    }
+
+    fn print_line(&mut self, column: usize, row: usize, line: &Line) -> io::Result<()> {
+        queue!(self.out, cursor::MoveTo(column as u16, row as u16))?;
+        for span in line {
+            let (foreground, background) = colors(span.style);
+            queue!(self.out, SetForegroundColor(foreground))?;
+            if let Some(background) = background {
+                queue!(self.out, SetBackgroundColor(background))?;
+            }
+            queue!(self.out, Print(&span.text), ResetColor)?;
+        }
+        Ok(())
+    }
+
+    // Let the human pick a move with the keyboard; None if they quit
+    pub fn read_move(&mut self, game: &Quorridor) -> io::Result<Option<Move>> {
+        let piece = game.player_pieces[game.active_player];
+        if let Selection::Square(..) = self.selection {
+            self.selection = Selection::Square(piece.x, piece.y);
+        }
+        let saved_help = std::mem::replace(&mut self.info.help, vec![
+            "arrows: move cursor   enter: play".to_string(),
+            "tab: pawn/wall mode   r/space: rotate wall".to_string(),
+            "p: show paths         q: quit".to_string(),
+        ]);
+        let result = loop {
+            self.draw(game, Some(self.selection))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            if is_quit(&key) {
+                break None;
+            }
+            if key.code == KeyCode::Char('p') {
+                self.info.show_paths = !self.info.show_paths;
+                continue;
+            }
+            let (dx, dy) = match key.code {
+                KeyCode::Left => (-2, 0),
+                KeyCode::Right => (2, 0),
+                KeyCode::Up => (0, 2),
+                KeyCode::Down => (0, -2),
+                _ => (0, 0),
+            };
+            self.selection = match (self.selection, key.code) {
+                (Selection::Square(..), KeyCode::Tab | KeyCode::Char('w')) => Selection::Wall(8, 8, Orientation::Horizontal),
+                (Selection::Wall(..), KeyCode::Tab | KeyCode::Char('w')) => Selection::Square(piece.x, piece.y),
+                (Selection::Wall(x, y, orientation), KeyCode::Char('r') | KeyCode::Char(' ')) => {
+                    let rotated = if orientation == Orientation::Horizontal { Orientation::Vertical } else { Orientation::Horizontal };
+                    Selection::Wall(x, y, rotated)
+                }
+                (Selection::Square(x, y), _) => {
+                    Selection::Square((x + dx).clamp(1, GRID_WIDTH as i64 - 2), (y + dy).clamp(1, GRID_HEIGHT as i64 - 2))
+                }
+                (Selection::Wall(x, y, orientation), _) => {
+                    Selection::Wall((x + dx).clamp(0, GRID_WIDTH as i64 - 3), (y + dy).clamp(0, GRID_HEIGHT as i64 - 3), orientation)
+                }
+            };
+            if key.code != KeyCode::Enter {
+                continue;
+            }
+            let chosen = match self.selection {
+                Selection::Square(x, y) => pawn_destinations(game).into_iter()
+                    .find(|&(square, _)| square == (x, y))
+                    .map(|(_, mov)| mov),
+                Selection::Wall(x, y, orientation) => Some(Move::PlaceWall(x, y, orientation))
+                    .filter(|mov| game.legal_moves().contains(mov)),
+            };
+            match chosen {
+                Some(mov) => break Some(mov),
+                None => self.message("That move is not legal"),
+            }
+        };
+        self.info.help = saved_help;
+        Ok(result)
+    }
+
+    // Wait up to `duration`, e.g. between AI moves; true if the user asked to quit
+    pub fn pause(&mut self, duration: Duration) -> io::Result<bool> {
+        let deadline = Instant::now() + duration;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() || !event::poll(left)? {
+                return Ok(false);
+            }
+            if let Event::Key(key) = event::read()? && key.kind != KeyEventKind::Release && is_quit(&key) {
+                return Ok(true);
+            }
+        }
+    }
+
+    pub fn wait_for_key(&mut self) -> io::Result<()> {
+        self.read_key().map(|_| ())
+    }
+
+    pub fn read_key(&mut self) -> io::Result<KeyEvent> {
+        loop {
+            if let Event::Key(key) = event::read()? && key.kind != KeyEventKind::Release {
+                return Ok(key);
+            }
+        }
+    }
+}
+
+fn is_quit(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcts::GameState;
+
+    fn text(line: &Line) -> String {
+        line.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_board_follows_grid_size() {
+        let game = Quorridor::default();
+        let board = render_board(&game, None, None);
+        assert_eq!(board.len(), GRID_HEIGHT + 1);
+        let width = line_width(&board[0]);
+        assert!(board.iter().all(|line| line_width(line) == width));
+        // Player 1's home row is drawn at the top, player 0's at the bottom
+        assert!(text(&board[1]).contains(" 1 "));
+        assert!(text(&board[GRID_HEIGHT - 2]).contains(" 0 "));
+
+        let destinations = board.iter().flatten().filter(|span| span.style == Style::Destination).count();
+        assert_eq!(destinations, pawn_destinations(&game).len());
+
+        // Both pawns run up column e, where every edge is critical
+        let overlay = path_overlay(&game);
+        let board = render_board(&game, None, Some(&overlay));
+        assert_eq!(board.iter().flatten().filter(|span| span.style == Style::Critical).count(), 8);
+    }
+
+    #[test]
+    fn test_walls_and_wall_cursor() {
+        let mut game = Quorridor::default();
+        game.make_move(&Move::PlaceWall(8, 8, Orientation::Horizontal));
+        let walls = |board: &Vec<Line>| board.iter().flatten().filter(|span| span.style == Style::Wall).count();
+        assert_eq!(walls(&render_board(&game, None, None)), 3);
+
+        // Crossing the placed wall is not allowed, so the cursor shows it as illegal
+        let board = render_board(&game, Some(Selection::Wall(10, 6, Orientation::Vertical)), None);
+        assert_eq!(board.iter().flatten().filter(|span| span.style == Style::BadWallCursor).count(), 3);
+        let board = render_board(&game, Some(Selection::Wall(2, 2, Orientation::Vertical)), None);
+        assert_eq!(board.iter().flatten().filter(|span| span.style == Style::WallCursor).count(), 3);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::quorridor::Quorridor;
+use crate::zobrist::{wall_cell_key, walls_remaining_key};
 use std::sync::Arc;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -49,8 +50,11 @@ pub fn place_wall(game: &mut Quorridor, x: i64, y: i64, orientation: Orientation
     let grid = Arc::make_mut(&mut game.grid);
     for (px, py) in wall.positions() {
         grid[py as usize][px as usize] = true;
+        game.zobrist ^= wall_cell_key(px, py);
     }
     
+    game.zobrist ^= walls_remaining_key(idx, game.walls_remaining[idx]);
     game.walls_remaining[idx] -= 1;
+    game.zobrist ^= walls_remaining_key(idx, game.walls_remaining[idx]);
     WallPlacementResult::Success
 }
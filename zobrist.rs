@@ -0,0 +1,218 @@
+use crate::quorridor::{Quorridor, GRID_WIDTH, GRID_HEIGHT};
+
+// Zobrist keys for every component of a position. The hash is the XOR of the
+// keys of everything present, so moves update it by XORing keys in and out:
+// - one key per player per pawn square
+// - one key per wall cell of the grid (a wall sets three cells)
+// - one key per player per walls-remaining count
+// - one key for player 1 to move
+
+// Counts above this share the last key; the standard game starts with 10 each
+pub const MAX_WALLS: usize = 20;
+
+pub struct ZobristKeys {
+    pawn: [[[u64; GRID_WIDTH]; GRID_HEIGHT]; 2],
+    wall_cell: [[u64; GRID_WIDTH]; GRID_HEIGHT],
+    walls_remaining: [[u64; MAX_WALLS + 1]; 2],
+    side_to_move: u64,
+}
+
+// SplitMix64, so the keys are fixed at compile time and identical across runs
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn generate_keys() -> ZobristKeys {
+    let mut seed = 0x5155_4F52_5249_444F;
+    let mut keys = ZobristKeys {
+        pawn: [[[0; GRID_WIDTH]; GRID_HEIGHT]; 2],
+        wall_cell: [[0; GRID_WIDTH]; GRID_HEIGHT],
+        walls_remaining: [[0; MAX_WALLS + 1]; 2],
+        side_to_move: 0,
+    };
+
+    let mut y = 0;
+    while y < GRID_HEIGHT {
+        let mut x = 0;
+        while x < GRID_WIDTH {
+            keys.pawn[0][y][x] = splitmix64(&mut seed);
+            keys.pawn[1][y][x] = splitmix64(&mut seed);
+            keys.wall_cell[y][x] = splitmix64(&mut seed);
+            x += 1;
+        }
+        y += 1;
+    }
+
+    let mut count = 0;
+    while count <= MAX_WALLS {
+        keys.walls_remaining[0][count] = splitmix64(&mut seed);
+        keys.walls_remaining[1][count] = splitmix64(&mut seed);
+        count += 1;
+    }
+
+    keys.side_to_move = splitmix64(&mut seed);
+    keys
+}
+
+pub static KEYS: ZobristKeys = generate_keys();
+
+pub fn pawn_key(player: usize, x: i64, y: i64) -> u64 {
+    if x < 0 || y < 0 || x >= GRID_WIDTH as i64 || y >= GRID_HEIGHT as i64 {
+        return 0;
+    }
+    KEYS.pawn[player][y as usize][x as usize]
+}
+
+pub fn wall_cell_key(x: i64, y: i64) -> u64 {
+    KEYS.wall_cell[y as usize][x as usize]
+}
+
+pub fn walls_remaining_key(player: usize, count: usize) -> u64 {
+    KEYS.walls_remaining[player][count.min(MAX_WALLS)]
+}
+
+pub fn side_to_move_key() -> u64 {
+    KEYS.side_to_move
+}
+
+// Hash computed from scratch; the incremental hash kept in Quorridor must always equal this
+pub fn full_hash(game: &Quorridor) -> u64 {
+    let mut hash = 0;
+    for (player, piece) in game.player_pieces.iter().enumerate() {
+        hash ^= pawn_key(player, piece.x, piece.y);
+        hash ^= walls_remaining_key(player, game.walls_remaining[player]);
+    }
+    for y in 0..GRID_HEIGHT {
+        for x in 0..GRID_WIDTH {
+            if game.grid[y][x] {
+                hash ^= KEYS.wall_cell[y][x];
+            }
+        }
+    }
+    if game.active_player == 1 {
+        hash ^= KEYS.side_to_move;
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wall::{Wall, Orientation};
+    use mcts::GameState;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+
+    // SplitMix64 stream; StdRng is far too slow in debug builds for millions of positions
+    fn below(state: &mut u64, n: u64) -> i64 {
+        (splitmix64(state) % n) as i64
+    }
+
+    // A random position and its 128-bit fingerprint, which is independent of the Zobrist keys
+    fn random_position(rng: &mut u64, base: &Quorridor) -> (Quorridor, u128) {
+        let mut game = base.clone();
+        let mut words = [0u64; 7];
+        loop {
+            for piece in game.player_pieces.iter_mut() {
+                piece.x = below(rng, 9) * 2 + 1;
+                piece.y = below(rng, 9) * 2 + 1;
+            }
+            if game.player_pieces[0] != game.player_pieces[1] {
+                break;
+            }
+        }
+
+        for _ in 0..below(rng, 21) {
+            let orientation = if below(rng, 2) == 0 { Orientation::Horizontal } else { Orientation::Vertical };
+            let wall = Wall { x: below(rng, 8) * 2, y: below(rng, 8) * 2, orientation };
+            if wall.positions().iter().all(|&(x, y)| !game.wall_collision(x, y)) {
+                for (x, y) in wall.positions() {
+                    game.grid[y as usize][x as usize] = true;
+                    let bit = y as usize * GRID_WIDTH + x as usize;
+                    words[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+
+        game.walls_remaining = [below(rng, 11) as usize, below(rng, 11) as usize];
+        game.active_player = below(rng, 2) as usize;
+        game.recompute_hash();
+
+        words[6] = (game.player_pieces[0].x as u64) | (game.player_pieces[0].y as u64) << 8
+            | (game.player_pieces[1].x as u64) << 16 | (game.player_pieces[1].y as u64) << 24
+            | (game.walls_remaining[0] as u64) << 32 | (game.walls_remaining[1] as u64) << 40
+            | (game.active_player as u64) << 48;
+        let mut halves = [0u64; 2];
+        for (salt, half) in halves.iter_mut().enumerate() {
+            let mut state = salt as u64;
+            for word in words {
+                state ^= word;
+                *half = splitmix64(&mut state);
+                state = *half;
+            }
+        }
+        (game, ((halves[0] as u128) << 64) | halves[1] as u128)
+    }
+
+    #[test]
+    fn test_default_hash_matches_full_hash() {
+        let game = Quorridor::default();
+        assert_eq!(game.zobrist, full_hash(&game));
+    }
+
+    #[test]
+    fn test_incremental_hash_matches_full_hash() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..200 {
+            let mut game = Quorridor::default();
+            for _ in 0..60 {
+                let moves = game.available_moves();
+                let Some(mov) = moves.choose(&mut rng) else { break };
+                game.make_move(mov);
+                assert_eq!(game.zobrist, full_hash(&game), "after {:?}", mov);
+            }
+        }
+    }
+
+    // Distinct positions among `count` random ones, and how many of them share a hash
+    fn count_collisions(count: usize) -> (usize, usize) {
+        let mut rng = 42;
+        let base = Quorridor::default();
+        let mut seen: Vec<(u64, u128)> = (0..count)
+            .map(|_| {
+                let (game, fingerprint) = random_position(&mut rng, &base);
+                (game.zobrist, fingerprint)
+            })
+            .collect();
+
+        seen.sort_unstable();
+        seen.dedup();
+        let collisions = seen.windows(2).filter(|w| w[0].0 == w[1].0).count();
+        (seen.len(), collisions)
+    }
+
+    #[test]
+    fn test_no_collisions_over_random_positions() {
+        const POSITIONS: usize = 5_000;
+        let (distinct, collisions) = count_collisions(POSITIONS);
+        // Any collision among a few thousand distinct 64-bit hashes points at a bad key table
+        assert!(distinct > POSITIONS / 2, "too few distinct positions: {}", distinct);
+        assert_eq!(collisions, 0);
+    }
+
+    // The real check on the collision rate; run with cargo test --release -- --ignored
+    #[test]
+    #[ignore]
+    fn test_collision_rate_over_millions_of_positions() {
+        const POSITIONS: usize = 2_000_000;
+        let (distinct, collisions) = count_collisions(POSITIONS);
+        // Expected collisions among ~2M distinct 64-bit hashes is about 1e-7
+        assert!(distinct > POSITIONS / 2, "too few distinct positions: {}", distinct);
+        assert_eq!(collisions, 0);
+    }
+}